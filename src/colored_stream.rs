@@ -1,19 +1,41 @@
 use crate::{Color, ColorableStream, Segment};
-use std::io::{self, Write as _};
+use std::{
+    env,
+    io::{self, Write as _},
+};
 
 pub struct ColoredStream<'a> {
     empty: bool,
     segment_start: bool,
     bg: Color,
+    hyperlinks: bool,
     inner: &'a mut dyn ColorableStream,
 }
 
+/// `POWERLINE_HYPERLINKS` turns OSC 8 hyperlinks on or off, otherwise guess from the terminal
+fn hyperlinks_supported() -> bool {
+    if let Some(value) = env::var_os("POWERLINE_HYPERLINKS") {
+        return !value.is_empty() && value != "0";
+    }
+    env::var_os("KITTY_WINDOW_ID").is_some()
+        || env::var_os("WEZTERM_EXECUTABLE").is_some()
+        || env::var("VTE_VERSION")
+            .ok()
+            .and_then(|version| version.parse::<u32>().ok())
+            .is_some_and(|version| version >= 5000)
+        || matches!(
+            env::var("TERM_PROGRAM").as_deref(),
+            Ok("iTerm.app" | "WezTerm" | "vscode")
+        )
+}
+
 impl<'a> ColoredStream<'a> {
     pub fn new(inner: &'a mut dyn ColorableStream) -> Self {
         Self {
             empty: true,
             segment_start: false,
             bg: Color::from_rgb(0, 0, 0),
+            hyperlinks: hyperlinks_supported(),
             inner,
         }
     }
@@ -55,6 +77,34 @@ impl<'a> ColoredStream<'a> {
             self.set_bg_inner(color)
         }
     }
+    /// Separate pieces of the current segment, keeping its background
+    pub fn thin_separator(&mut self, fg: Color) -> io::Result<()> {
+        self.set_fg(fg)?;
+        write!(self, "")
+    }
+    /// Text written until [`ColoredStream::end_link`] links to `url`, plain text if
+    /// the terminal doesn't support hyperlinks
+    pub fn start_link(&mut self, url: &str) -> io::Result<()> {
+        if !self.hyperlinks {
+            return Ok(());
+        }
+        // anything but printable ASCII would end the escape sequence early
+        let url = url.bytes().fold(String::new(), |mut url, byte| {
+            if byte.is_ascii_graphic() {
+                url.push(byte as char);
+            } else {
+                url.push_str(&format!("%{:02X}", byte));
+            }
+            url
+        });
+        write!(self.inner, "\x01\x1B]8;;{}\x1B\\\x02", url)
+    }
+    pub fn end_link(&mut self) -> io::Result<()> {
+        if !self.hyperlinks {
+            return Ok(());
+        }
+        write!(self.inner, "\x01\x1B]8;;\x1B\\\x02")
+    }
     pub fn write_segment(&mut self, segment: &mut dyn Segment) -> io::Result<()> {
         self.segment_finished();
         segment.write(self)?;
@@ -72,7 +122,7 @@ impl<'a> ColoredStream<'a> {
     }
     pub fn new_line(&mut self) -> io::Result<()> {
         self.end_line()?;
-        writeln!(self)?;
+        writeln!(self.inner)?;
         self.empty = true;
         Ok(())
     }
@@ -83,16 +133,96 @@ impl<'a> ColoredStream<'a> {
     }
 }
 
+/// Make text safe to put into the prompt
+///
+/// Control characters (and bidi overrides) are escaped so they can't move the cursor
+/// or inject terminal sequences, bytes that aren't valid UTF-8 are shown as `\xff`.
+pub fn sanitize(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if c.is_control() && (c as u32) < 0x80 => {
+                    out.push_str(&format!("\\x{:02x}", c as u32))
+                }
+                c if c.is_control()
+                    || ('\u{202a}'..='\u{202e}').contains(&c)
+                    || ('\u{2066}'..='\u{2069}').contains(&c) =>
+                {
+                    out.extend(c.escape_unicode())
+                }
+                c => out.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            out.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    out
+}
+
 impl<'a> std::io::Write for ColoredStream<'a> {
+    /// Segment text is sanitized, colors and separators are written to `inner` directly
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         if self.segment_start {
             self.start_segment(self.bg)?;
         }
         self.empty = false;
-        self.inner.write(buf)
+        self.inner.write_all(sanitize(buf).as_bytes())?;
+        Ok(buf.len())
     }
 
     fn flush(&mut self) -> io::Result<()> {
         self.inner.flush()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::sanitize;
+
+    #[test]
+    fn escapes_ascii_controls() {
+        assert_eq!(sanitize(b"a\x1b[31mb"), "a\\x1b[31mb");
+        assert_eq!(sanitize(b"one\ntwo\r\tthree"), "one\\ntwo\\r\\tthree");
+        assert_eq!(sanitize(b"del\x7f"), "del\\x7f");
+        assert_eq!(sanitize(b"\x00\x07"), "\\x00\\x07");
+    }
+
+    #[test]
+    fn escapes_c1_controls() {
+        // CSI as a single character
+        assert_eq!(sanitize("a\u{9b}31m".as_bytes()), "a\\u{9b}31m");
+        assert_eq!(sanitize("\u{85}".as_bytes()), "\\u{85}");
+    }
+
+    #[test]
+    fn escapes_bidi_overrides() {
+        assert_eq!(sanitize("\u{202e}txt.exe".as_bytes()), "\\u{202e}txt.exe");
+        assert_eq!(sanitize("\u{2066}a\u{2069}".as_bytes()), "\\u{2066}a\\u{2069}");
+    }
+
+    #[test]
+    fn escapes_invalid_utf8() {
+        assert_eq!(sanitize(b"caf\xe9"), "caf\\xe9");
+        assert_eq!(sanitize(b"\xff\xfeok"), "\\xff\\xfeok");
+        // a truncated multi-byte sequence
+        assert_eq!(sanitize(b"x\xe2\x82"), "x\\xe2\\x82");
+    }
+
+    #[test]
+    fn keeps_printable_text() {
+        for text in [
+            "plain text",
+            "~/src/powerline",
+            "größe naïve 日本語",
+            "☸ 🐳 ⏱ 🕐",
+            "\u{e0b0}\u{e0b1}\u{e0a0}",
+        ] {
+            assert_eq!(sanitize(text.as_bytes()), text);
+        }
+    }
+}
@@ -32,24 +32,63 @@ struct Args {
     #[argh(option, short = 'e', long = "exit-code")]
     /// value for exit-code segment
     exit_code: Option<i32>,
+
+    #[argh(option, long = "pipestatus")]
+    /// comma separated exit codes of the stages of the last pipeline
+    pipestatus: Option<String>,
+
+    #[argh(option, long = "duration-ms")]
+    /// how long the last command took, in milliseconds
+    duration_ms: Option<u64>,
+
+    #[argh(option, long = "jobs")]
+    /// number of running background jobs
+    jobs: Option<u32>,
+
+    #[argh(option, long = "jobs-stopped")]
+    /// number of stopped background jobs
+    jobs_stopped: Option<u32>,
+
+    #[argh(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(argh::FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Kube(segments::KubeCommand),
 }
 
 fn main() -> io::Result<()> {
     let args: Args = argh::from_env();
 
-    let mut time = segments::Time;
+    if let Some(Command::Kube(cmd)) = args.command {
+        if let Err(e) = cmd.run() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let mut time = segments::Time::new();
     let mut hostname = segments::Hostname::new();
+    let mut container = segments::Container::new();
     let mut user = segments::User::new();
-    let mut cwd = segments::Cwd;
+    let mut shell = segments::Shell::new();
+    let mut cwd = segments::Cwd::new();
     let mut git = segments::Git::new();
     let mut openstack = segments::Openstack::new();
     let mut k8s = segments::K8s::new();
-    let mut exitcode = segments::ExitCode::new(args.exit_code);
+    let mut exitcode = segments::ExitCode::new(args.exit_code, args.pipestatus.as_deref());
+    let mut duration = segments::Duration::new(args.duration_ms);
+    let mut jobs = segments::Jobs::new(args.jobs, args.jobs_stopped);
 
     let segments = [
         &mut time as &mut dyn Segment,
         &mut hostname,
+        &mut container,
         &mut user,
+        &mut shell,
         &mut cwd,
         &mut git,
         &mut openstack,
@@ -66,6 +105,8 @@ fn main() -> io::Result<()> {
     }
     stream.new_line()?;
     stream.write_segment(&mut exitcode)?;
+    stream.write_segment(&mut duration)?;
+    stream.write_segment(&mut jobs)?;
     stream.end_line()?;
     stream.reset()?;
     write!(stream, " ")?;
@@ -0,0 +1,33 @@
+use std::env;
+
+/// Rewrite rules read from an environment variable
+///
+/// The variable holds comma separated `pattern=replacement` pairs, a `*` in the pattern
+/// matches anything and is substituted for `*` in the replacement, e.g.
+/// `kube.*.caas.example.com=*,keystone.example.com=prod`. The first matching rule wins.
+pub struct Aliases(Vec<(String, String)>);
+
+impl Aliases {
+    pub fn from_env(name: &str) -> Self {
+        let rules = env::var(name)
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|rule| rule.split_once('='))
+            .map(|(pattern, replacement)| (pattern.trim().to_string(), replacement.to_string()))
+            .collect();
+        Self(rules)
+    }
+
+    pub fn apply(&self, value: &str) -> Option<String> {
+        self.0
+            .iter()
+            .find_map(|(pattern, replacement)| match pattern.split_once('*') {
+                Some((prefix, suffix)) => value
+                    .strip_prefix(prefix)
+                    .and_then(|rest| rest.strip_suffix(suffix))
+                    .map(|matched| replacement.replace('*', matched)),
+                None if pattern == value => Some(replacement.clone()),
+                None => None,
+            })
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs, io,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Size and modification time of a file a cached value was derived from
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FileStamp {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> io::Result<Self> {
+        let meta = fs::metadata(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            size: meta.len(),
+            modified: meta.modified()?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Entry<T> {
+    key: String,
+    files: Vec<FileStamp>,
+    value: T,
+}
+
+#[derive(Serialize)]
+struct EntryRef<'a, T> {
+    key: &'a str,
+    files: &'a [FileStamp],
+    value: &'a T,
+}
+
+/// On-disk cache of a value derived from a set of files
+///
+/// Stored under `$XDG_CACHE_HOME/powerline/<dir>`, the entry is considered stale
+/// as soon as the path, size or mtime of any of the contributing files changes,
+/// or when it is older than the optional maximum age.
+pub struct Cache {
+    /// Stored in the entry too, the file name is just a hash of it and the paths
+    key: String,
+    file: PathBuf,
+    stamps: Vec<FileStamp>,
+    max_age: Option<Duration>,
+}
+
+impl Cache {
+    /// `None` if there is no cache directory or any of `paths` can't be stat'ed
+    pub fn new(dir: &str, key: &str, paths: &[&Path]) -> Option<Self> {
+        let stamps = paths
+            .iter()
+            .map(|path| FileStamp::of(path))
+            .collect::<io::Result<Vec<_>>>()
+            .ok()?;
+        let mut name = key.as_bytes().to_vec();
+        for path in paths {
+            name.push(0);
+            name.extend_from_slice(path.as_os_str().as_bytes());
+        }
+        let file = dirs::cache_dir()?
+            .join("powerline")
+            .join(dir)
+            .join(format!("{:08x}.json", super::fnv1a(&name)));
+        Some(Self {
+            key: key.to_string(),
+            file,
+            stamps,
+            max_age: None,
+        })
+    }
+
+    /// For values that depend on more than files, like DNS answers
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn get<T: DeserializeOwned>(&self) -> Option<T> {
+        if let Some(max_age) = self.max_age {
+            let modified = fs::metadata(&self.file)
+                .and_then(|meta| meta.modified())
+                .ok()?;
+            if modified.elapsed().map_or(true, |age| age > max_age) {
+                return None;
+            }
+        }
+        let data = fs::read(&self.file).ok()?;
+        let entry: Entry<T> = serde_json::from_slice(&data).ok()?;
+        if entry.key == self.key && entry.files == self.stamps {
+            Some(entry.value)
+        } else {
+            None
+        }
+    }
+
+    /// Failing to write the cache is not an error, the value is recomputed next time
+    pub fn put<T: Serialize>(&self, value: &T) {
+        let _ = self.try_put(value);
+    }
+
+    fn try_put<T: Serialize>(&self, value: &T) -> io::Result<()> {
+        let entry = EntryRef {
+            key: &self.key,
+            files: &self.stamps,
+            value,
+        };
+        let data = serde_json::to_vec(&entry)?;
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // write to a temporary file first so concurrent prompts never read a partial entry
+        let tmp = self
+            .file
+            .with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &self.file)
+    }
+}
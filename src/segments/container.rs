@@ -0,0 +1,149 @@
+use crate::{Color, ColoredStream, Segment};
+use std::{env, fs, io::Write as _, os::unix::fs::MetadataExt, path::Path};
+
+/// Where the shell runs, if it's not directly on a physical host
+enum Environment {
+    Docker,
+    Podman,
+    Toolbox,
+    Distrobox,
+    /// Reported by the `container` variable (systemd-nspawn, lxc, ...) or cgroups
+    Other(String),
+    Chroot,
+    /// Hypervisor vendor
+    Vm(String),
+}
+
+pub struct Container {
+    environment: Option<Environment>,
+    /// Container or image name, when the runtime tells us
+    name: Option<String>,
+}
+
+fn read_trimmed<P: AsRef<Path>>(path: P) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+}
+
+/// `key="value"` from podman's `/run/.containerenv`
+fn containerenv_value(containerenv: &str, key: &str) -> Option<String> {
+    containerenv.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix('=')?;
+        Some(value.trim_matches('"').to_string()).filter(|value| !value.is_empty())
+    })
+}
+
+/// Runtime mentioned in the cgroup paths of init
+fn cgroup_hint() -> Option<String> {
+    let cgroup = fs::read_to_string("/proc/1/cgroup").ok()?;
+    ["kubepods", "docker", "containerd", "lxc", "libpod"]
+        .iter()
+        .find(|hint| cgroup.contains(*hint))
+        .map(|hint| hint.to_string())
+}
+
+/// `/` is not the root of init
+fn is_chroot() -> bool {
+    match (fs::metadata("/"), fs::metadata("/proc/1/root")) {
+        (Ok(root), Ok(init_root)) => root.dev() != init_root.dev() || root.ino() != init_root.ino(),
+        _ => false,
+    }
+}
+
+/// Hypervisor from the DMI tables
+fn vm_vendor() -> Option<String> {
+    let dmi = |file| read_trimmed(Path::new("/sys/class/dmi/id").join(file)).unwrap_or_default();
+    let (sys_vendor, product_name) = (dmi("sys_vendor"), dmi("product_name"));
+    // Microsoft also makes physical machines, like the Surface laptops
+    if sys_vendor == "Microsoft Corporation" && product_name == "Virtual Machine" {
+        return Some("hyper-v".to_string());
+    }
+    let dmi = [sys_vendor, product_name, dmi("board_vendor")].join(" ");
+    let vendors = [
+        ("QEMU", "qemu"),
+        ("KVM", "kvm"),
+        ("VMware", "vmware"),
+        ("VirtualBox", "virtualbox"),
+        ("Xen", "xen"),
+        ("Amazon EC2", "ec2"),
+        ("Google Compute Engine", "gce"),
+        ("Parallels", "parallels"),
+    ];
+    vendors
+        .iter()
+        .find(|(needle, _)| dmi.contains(needle))
+        .map(|(_, name)| name.to_string())
+        .or_else(|| read_trimmed("/sys/hypervisor/type").filter(|_| !is_xen_dom0()))
+}
+
+/// The Xen control domain runs on the hypervisor too, but it's the host
+fn is_xen_dom0() -> bool {
+    read_trimmed("/proc/xen/capabilities").is_some_and(|caps| caps.contains("control_d"))
+}
+
+fn detect() -> (Option<Environment>, Option<String>) {
+    if let Ok(containerenv) = fs::read_to_string("/run/.containerenv") {
+        let name = containerenv_value(&containerenv, "name")
+            .or_else(|| containerenv_value(&containerenv, "image"));
+        let environment = if Path::new("/run/.toolboxenv").exists() {
+            Environment::Toolbox
+        } else if env::var_os("DISTROBOX_ENTER_PATH").is_some() {
+            Environment::Distrobox
+        } else {
+            Environment::Podman
+        };
+        return (Some(environment), name);
+    }
+    if Path::new("/.dockerenv").exists() {
+        return (Some(Environment::Docker), None);
+    }
+    if let Some(container) = env::var("container").ok().filter(|c| !c.is_empty()) {
+        let name = env::var("CONTAINER_ID").ok();
+        return (Some(Environment::Other(container)), name);
+    }
+    if let Some(hint) = cgroup_hint() {
+        return (Some(Environment::Other(hint)), None);
+    }
+    if is_chroot() {
+        return (Some(Environment::Chroot), None);
+    }
+    (vm_vendor().map(Environment::Vm), None)
+}
+
+impl Container {
+    pub fn new() -> Self {
+        let (environment, name) = detect();
+        Self { environment, name }
+    }
+}
+
+impl Segment for Container {
+    fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()> {
+        let environment = match self.environment {
+            Some(ref environment) => environment,
+            None => return Ok(()),
+        };
+        let bg = match environment {
+            Environment::Chroot => Color::from_rgb(120, 80, 0),
+            Environment::Vm(_) => Color::from_rgb(60, 60, 110),
+            _ => Color::from_rgb(0, 90, 160),
+        };
+        w.set_bg(bg)?;
+        w.set_fg(Color::from_rgb(255, 255, 255))?;
+        match environment {
+            Environment::Docker => write!(w, " 🐳")?,
+            Environment::Podman => write!(w, " 🦭")?,
+            Environment::Toolbox => write!(w, " 🧰")?,
+            Environment::Distrobox => write!(w, " 📦")?,
+            Environment::Other(kind) => write!(w, " 📦 {}", kind)?,
+            Environment::Chroot => write!(w, " ⛓ chroot")?,
+            Environment::Vm(vendor) => write!(w, " 🖥 {}", vendor)?,
+        }
+        if let Some(ref name) = self.name {
+            write!(w, " {}", name)?;
+        }
+        write!(w, " ")
+    }
+}
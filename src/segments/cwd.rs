@@ -1,19 +1,335 @@
-use crate::{Color, ColoredStream, Segment};
-use std::io::Write;
+use crate::{colored_stream::sanitize, Color, ColoredStream, Segment};
+use std::{
+    env,
+    ffi::CString,
+    fmt, fs,
+    io::Write,
+    os::unix::{ffi::OsStrExt, fs::MetadataExt},
+    path::{Component, Path, PathBuf},
+};
 
-pub struct Cwd;
+/// A way to shorten the path, `POWERLINE_CWD_SHORTEN` holds a comma separated list
+/// applied in order, e.g. `git,fish` or `last:3,width:40`
+enum Shorten {
+    /// Start at the root of the git repository: `powerline/src/segments`
+    GitRoot,
+    /// Keep only the last N directories: `…/src/segments`
+    Last(usize),
+    /// Abbreviate all but the last directory like fish does: `~/s/p/segments`
+    Fish,
+    /// Replace directories in the middle with `…` until the path fits in N characters
+    Width(usize),
+}
+
+impl Shorten {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().split_once(':') {
+            Some(("last", n)) => n.parse().ok().map(Shorten::Last),
+            Some(("width", n)) => n.parse().ok().map(Shorten::Width),
+            Some(_) => None,
+            None => match value.trim() {
+                "git" => Some(Shorten::GitRoot),
+                "fish" => Some(Shorten::Fish),
+                _ => None,
+            },
+        }
+    }
+
+    fn from_env() -> Vec<Self> {
+        env::var("POWERLINE_CWD_SHORTEN")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(Shorten::parse)
+            .collect()
+    }
+}
+
+/// The path as it is displayed, `prefix` stands for everything before `parts`
+///
+/// An empty `prefix` means the filesystem root, a prefix ending with `:` is not followed by `/`.
+struct DisplayPath {
+    prefix: String,
+    parts: Vec<String>,
+}
+
+fn components(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(sanitize(name.as_bytes())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Expand a leading `~` and `$VAR`/`${VAR}` anywhere, `None` if a variable is not set
+fn expand(path: &str) -> Option<PathBuf> {
+    let mut expanded = String::new();
+    let mut rest = path.trim();
+    if let Some(stripped) = rest.strip_prefix('~') {
+        expanded.push_str(dirs::home_dir()?.to_str()?);
+        rest = stripped;
+    }
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let (name, after) = match rest.strip_prefix('{') {
+            Some(braced) => braced.split_once('}')?,
+            None => {
+                let end = rest
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                rest.split_at(end)
+            }
+        };
+        expanded.push_str(&env::var(name).ok()?);
+        rest = after;
+    }
+    expanded.push_str(rest);
+    Some(PathBuf::from(expanded))
+}
+
+/// Home directory as `~` plus `POWERLINE_CWD_ALIASES`, comma separated `path=label` pairs
+/// like `/srv/deploy/customer-a=📦A,$GOPATH/src/github.com/org=org:`
+///
+/// Home comes first, so a user rule for the same directory takes precedence.
+fn path_aliases() -> Vec<(PathBuf, String)> {
+    let rules = env::var("POWERLINE_CWD_ALIASES").unwrap_or_default();
+    let user_aliases = rules
+        .split(',')
+        .filter_map(|rule| rule.split_once('='))
+        .filter_map(|(path, label)| Some((expand(path)?, label.to_string())));
+    dirs::home_dir()
+        .map(|homedir| (homedir, "~".to_string()))
+        .into_iter()
+        .chain(user_aliases)
+        .collect()
+}
+
+impl DisplayPath {
+    /// The longest of `aliases` matching `path` becomes the prefix, the last one on a tie
+    fn new(path: &Path, aliases: &[(PathBuf, String)]) -> Self {
+        let alias = aliases
+            .iter()
+            .filter_map(|(alias, label)| Some((path.strip_prefix(alias).ok()?, alias, label)))
+            .max_by_key(|(_, alias, _)| alias.components().count());
+        match alias {
+            Some((stripped, _, label)) => Self {
+                prefix: label.clone(),
+                parts: components(stripped),
+            },
+            None => Self {
+                prefix: String::new(),
+                parts: components(path),
+            },
+        }
+    }
+
+    fn shorten(&mut self, how: &Shorten, path: &Path) {
+        match *how {
+            Shorten::GitRoot => {
+                let repo = match git2::Repository::discover(path) {
+                    Ok(repo) => repo,
+                    Err(_) => return,
+                };
+                let root = match repo.workdir() {
+                    Some(root) => root,
+                    None => return,
+                };
+                // `workdir()` is a physical path while ours may go through symlinks
+                let (path, root) = match (fs::canonicalize(path), fs::canonicalize(root)) {
+                    (Ok(path), Ok(root)) => (path, root),
+                    _ => return,
+                };
+                if let (Ok(stripped), Some(name)) = (path.strip_prefix(&root), root.file_name()) {
+                    self.prefix = sanitize(name.as_bytes());
+                    self.parts = components(stripped);
+                }
+            }
+            Shorten::Last(n) => {
+                if self.parts.len() > n {
+                    self.prefix = "…".to_string();
+                    self.parts.drain(..self.parts.len() - n);
+                }
+            }
+            Shorten::Fish => {
+                let last = self.parts.len().saturating_sub(1);
+                for part in &mut self.parts[..last] {
+                    // keep the dot of hidden directories: `.config` -> `.c`
+                    let len = if part.starts_with('.') { 2 } else { 1 };
+                    *part = part.chars().take(len).collect();
+                }
+            }
+            Shorten::Width(width) => {
+                let ellipsis = "…".to_string();
+                let mut tail = self.parts.split_off(self.parts.len() / 2);
+                let mut head = std::mem::take(&mut self.parts);
+                let mut shortened = false;
+                loop {
+                    self.parts = head
+                        .iter()
+                        .chain(shortened.then_some(&ellipsis))
+                        .chain(tail.iter())
+                        .cloned()
+                        .collect();
+                    if self.to_string().chars().count() <= width {
+                        break;
+                    }
+                    // drop directories closest to the middle, keep the first and the last one
+                    if head.len() > 1 && head.len() >= tail.len() {
+                        head.pop();
+                    } else if tail.len() > 1 {
+                        tail.remove(0);
+                    } else {
+                        break;
+                    }
+                    shortened = true;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for DisplayPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.prefix.is_empty() {
+            write!(f, "/{}", self.parts.join("/"))
+        } else if self.parts.is_empty() {
+            write!(f, "{}", self.prefix)
+        } else if self.prefix.ends_with(':') {
+            write!(f, "{}{}", self.prefix, self.parts.join("/"))
+        } else {
+            write!(f, "{}/{}", self.prefix, self.parts.join("/"))
+        }
+    }
+}
+
+/// The logical `$PWD`, which keeps symlinks the way they were typed, if it still refers
+/// to the current directory, the physical path otherwise
+///
+/// When the current directory was deleted only `$PWD` is left, the flag is then `true`.
+fn working_dir() -> Option<(PathBuf, bool)> {
+    let pwd = env::var_os("PWD")
+        .map(PathBuf::from)
+        .filter(|pwd| pwd.is_absolute());
+    match env::current_dir() {
+        Ok(physical) => {
+            let logical = pwd.filter(|pwd| same_file(pwd, &physical));
+            Some((logical.unwrap_or(physical), false))
+        }
+        Err(_) => pwd.map(|pwd| (pwd, true)),
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+fn is_writable(path: &Path) -> bool {
+    match CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 },
+        Err(_) => false,
+    }
+}
+
+pub struct Cwd {
+    path: Option<DisplayPath>,
+    split: bool,
+    deleted: bool,
+    read_only: bool,
+    url: Option<String>,
+}
+
+impl Cwd {
+    pub fn new() -> Self {
+        let (wd, deleted) = match working_dir() {
+            Some(wd) => wd,
+            None => {
+                return Self {
+                    path: None,
+                    split: false,
+                    deleted: false,
+                    read_only: false,
+                    url: None,
+                }
+            }
+        };
+        let mut path = DisplayPath::new(&wd, &path_aliases());
+        for how in Shorten::from_env() {
+            path.shorten(&how, &wd);
+        }
+        let url = if deleted {
+            None
+        } else {
+            let host = hostname::get().unwrap_or_default();
+            Some(format!(
+                "file://{}{}",
+                super::percent_encode_path(host.as_bytes()),
+                super::percent_encode_path(wd.as_os_str().as_bytes())
+            ))
+        };
+        Self {
+            path: Some(path),
+            split: super::env_flag("POWERLINE_CWD_SPLIT"),
+            deleted,
+            read_only: !deleted && !is_writable(&wd),
+            url,
+        }
+    }
+}
 
 impl Segment for Cwd {
     fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()> {
-        let wd = std::env::current_dir().unwrap_or(std::path::PathBuf::from(" /?/?/? "));
-        w.set_bg(Color::from_rgb(60, 60, 60))?;
+        let path = match self.path {
+            Some(ref path) => path,
+            None => {
+                w.set_bg(Color::from_rgb(60, 60, 60))?;
+                w.set_fg(Color::from_rgb(210, 210, 210))?;
+                return write!(w, " /?/?/? ");
+            }
+        };
+        if self.deleted {
+            w.set_bg(Color::from_rgb(120, 30, 30))?;
+        } else {
+            w.set_bg(Color::from_rgb(60, 60, 60))?;
+        }
         w.set_fg(Color::from_rgb(210, 210, 210))?;
-        if let Some(homedir) = dirs::home_dir() {
-            if let Ok(stripped) = wd.strip_prefix(&homedir) {
-                write!(w, " ~/{} ", stripped.display())?;
-                return Ok(());
+        if self.read_only {
+            write!(w, " 🔒")?;
+        }
+        if let Some(ref url) = self.url {
+            w.start_link(url)?;
+        }
+        if self.split {
+            let root = if path.prefix.is_empty() {
+                "/"
+            } else {
+                &path.prefix
+            };
+            let pieces = std::iter::once(root).chain(path.parts.iter().map(String::as_str));
+            let last = path.parts.len();
+            for (i, piece) in pieces.enumerate() {
+                if i > 0 {
+                    w.thin_separator(Color::from_rgb(120, 120, 120))?;
+                }
+                if i == last {
+                    w.set_fg(Color::from_rgb(255, 255, 255))?;
+                } else {
+                    w.set_fg(Color::from_rgb(210, 210, 210))?;
+                }
+                write!(w, " {} ", piece)?;
             }
+        } else {
+            write!(w, " {} ", path)?;
+        }
+        if self.url.is_some() {
+            w.end_link()?;
+        }
+        if self.deleted {
+            write!(w, "✗ deleted ")?;
         }
-        write!(w, " {} ", wd.display())
+        Ok(())
     }
 }
@@ -0,0 +1,50 @@
+use crate::{Color, ColoredStream, Segment};
+use std::{env, io::Write};
+
+pub struct Duration {
+    /// How long the last command took, `None` when it's below the threshold
+    millis: Option<u64>,
+}
+
+/// `850ms`, `4.2s`, `1m23s`, `2h05m`
+fn format_duration(millis: u64) -> String {
+    let seconds = millis / 1000;
+    match seconds {
+        s if s >= 3600 => format!("{}h{:02}m", s / 3600, s % 3600 / 60),
+        s if s >= 60 => format!("{}m{:02}s", s / 60, s % 60),
+        s if s >= 10 => format!("{}s", s),
+        s if s >= 1 => format!("{}.{}s", s, millis % 1000 / 100),
+        _ => format!("{}ms", millis),
+    }
+}
+
+impl Duration {
+    pub fn new(millis: Option<u64>) -> Self {
+        // `POWERLINE_DURATION_THRESHOLD`, in seconds
+        let threshold = env::var("POWERLINE_DURATION_THRESHOLD")
+            .ok()
+            .and_then(|threshold| threshold.parse::<f64>().ok())
+            .unwrap_or(2.0);
+        Self {
+            millis: millis.filter(|&millis| millis as f64 >= threshold * 1000.0),
+        }
+    }
+}
+
+impl Segment for Duration {
+    fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()> {
+        let millis = match self.millis {
+            Some(millis) => millis,
+            None => return Ok(()),
+        };
+        let (bg, fg) = match millis / 1000 {
+            s if s >= 600 => (Color::from_rgb(150, 0, 0), Color::from_rgb(255, 255, 255)),
+            s if s >= 60 => (Color::from_rgb(200, 100, 0), Color::from_rgb(0, 0, 0)),
+            s if s >= 10 => (Color::from_rgb(180, 160, 0), Color::from_rgb(0, 0, 0)),
+            _ => (Color::from_rgb(80, 80, 80), Color::from_rgb(200, 200, 200)),
+        };
+        w.set_bg(bg)?;
+        w.set_fg(fg)?;
+        write!(w, " ⏱ {} ", format_duration(millis))
+    }
+}
@@ -3,30 +3,108 @@ use std::io::Write;
 
 pub struct ExitCode {
     code: Option<i32>,
+    /// Status of every stage of the last pipeline, from `--pipestatus 0,1,0`
+    pipestatus: Vec<i32>,
+}
+
+/// Names of the signals on Linux, with whether their default action dumps core
+const SIGNALS: [(&str, bool); 31] = [
+    ("SIGHUP", false),
+    ("SIGINT", false),
+    ("SIGQUIT", true),
+    ("SIGILL", true),
+    ("SIGTRAP", true),
+    ("SIGABRT", true),
+    ("SIGBUS", true),
+    ("SIGFPE", true),
+    ("SIGKILL", false),
+    ("SIGUSR1", false),
+    ("SIGSEGV", true),
+    ("SIGUSR2", false),
+    ("SIGPIPE", false),
+    ("SIGALRM", false),
+    ("SIGTERM", false),
+    ("SIGSTKFLT", false),
+    ("SIGCHLD", false),
+    ("SIGCONT", false),
+    ("SIGSTOP", false),
+    ("SIGTSTP", false),
+    ("SIGTTIN", false),
+    ("SIGTTOU", false),
+    ("SIGURG", false),
+    ("SIGXCPU", true),
+    ("SIGXFSZ", true),
+    ("SIGVTALRM", false),
+    ("SIGPROF", false),
+    ("SIGWINCH", false),
+    ("SIGIO", false),
+    ("SIGPWR", false),
+    ("SIGSYS", true),
+];
+
+/// What the shell means by `code`, if it's more than just a failure
+fn describe(code: i32) -> Option<String> {
+    match code {
+        126 => Some("not executable".into()),
+        127 => Some("not found".into()),
+        129..=159 => {
+            let (name, core) = SIGNALS[(code - 129) as usize];
+            Some(if core {
+                format!("{}+core", name)
+            } else {
+                name.into()
+            })
+        }
+        _ => None,
+    }
+}
+
+fn write_code(w: &mut ColoredStream, code: i32) -> std::io::Result<()> {
+    match describe(code) {
+        Some(description) => write!(w, " {} {} ", code, description),
+        None => write!(w, " {} ", code),
+    }
+}
+
+fn code_color(code: Option<i32>) -> Color {
+    match code {
+        Some(0) => Color::from_rgb(0, 100, 0),
+        Some(_) => Color::from_rgb(100, 0, 0),
+        None => Color::from_rgb(0, 0, 100),
+    }
 }
 
 impl ExitCode {
-    pub fn new(code: Option<i32>) -> Self {
-        Self { code }
+    pub fn new(code: Option<i32>, pipestatus: Option<&str>) -> Self {
+        let pipestatus = pipestatus
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|status| status.trim().parse().ok())
+            .collect();
+        Self { code, pipestatus }
     }
 }
 
 impl Segment for ExitCode {
     fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()> {
-        let bg = if let Some(code) = self.code {
-            if code == 0 {
-                Color::from_rgb(0, 100, 0)
-            } else {
-                Color::from_rgb(100, 0, 0)
+        // a single stage says nothing more than the exit code
+        if self.pipestatus.len() > 1 && self.pipestatus.iter().any(|&status| status != 0) {
+            for (i, &status) in self.pipestatus.iter().enumerate() {
+                if i == 0 {
+                    w.set_bg(code_color(Some(status)))?;
+                } else {
+                    w.start_segment(code_color(Some(status)))?;
+                }
+                w.set_fg(Color::from_rgb(200, 200, 200))?;
+                write_code(w, status)?;
             }
-        } else {
-            Color::from_rgb(0, 0, 100)
-        };
-        w.set_bg(bg)?;
+            return Ok(());
+        }
+        w.set_bg(code_color(self.code))?;
         w.set_fg(Color::from_rgb(200, 200, 200))?;
         match self.code {
             Some(0) | None => write!(w, "   "),
-            Some(code) => write!(w, " {} ", code),
+            Some(code) => write_code(w, code),
         }
     }
 }
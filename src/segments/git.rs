@@ -7,6 +7,7 @@ pub struct Git(Option<GitInner>);
 pub struct GitInner {
     state: Result<State, git2::Error>,
     statuses: Result<Statuses, git2::Error>,
+    web_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -83,7 +84,7 @@ impl Segment for Statuses {
     }
 }
 
-pub fn get_head_branch(repo: &git2::Repository) -> Result<Option<Branch>, git2::Error> {
+pub fn get_head_branch(repo: &git2::Repository) -> Result<Option<Branch<'_>>, git2::Error> {
     let branches = repo.branches(Some(BranchType::Local))?;
     for branch in branches {
         let (branch, _ /*branch_type*/) = branch?;
@@ -100,7 +101,7 @@ fn get_state(repo: &git2::Repository) -> Result<State, git2::Error> {
         let target = head.peel(git2::ObjectType::Any)?;
         let short_id = target.short_id()?;
         Ok(State::Detached(short_id))
-    } else if let Some(branch) = get_head_branch(&repo)? {
+    } else if let Some(branch) = get_head_branch(repo)? {
         let local = branch.get().target().ok_or(git2::Error::new(
             ErrorCode::NotFound,
             ErrorClass::Object,
@@ -169,6 +170,38 @@ fn get_statuses(git: &Repository) -> Result<Statuses, git2::Error> {
     })
 }
 
+/// Browsable URL of a remote, e.g. `git@github.com:org/repo.git` -> `https://github.com/org/repo`
+fn web_url(url: &str) -> Option<String> {
+    let (authority, path) = match url.split_once("://") {
+        Some(("file", _)) => return None,
+        Some((_, rest)) => rest.split_once('/')?,
+        // scp-like syntax `[user@]host:path`
+        None => match url.split_once(':')? {
+            (authority, _) if authority.contains('/') => return None,
+            authority_path => authority_path,
+        },
+    };
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let (scheme, host) = if url.starts_with("http://") {
+        ("http", host)
+    } else if url.starts_with("https://") {
+        ("https", host)
+    } else {
+        // the ssh/git port has nothing to do with the web interface
+        ("https", host.split(':').next()?)
+    };
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    Some(format!("{}://{}/{}", scheme, host, path))
+}
+
+fn get_web_url(repo: &Repository) -> Option<String> {
+    let remote = repo.find_remote("origin").ok()?;
+    web_url(remote.url()?)
+}
+
 impl Git {
     pub fn new() -> Self {
         let repo = match git2::Repository::discover(".") {
@@ -178,7 +211,12 @@ impl Git {
 
         let state = get_state(&repo);
         let statuses = get_statuses(&repo);
-        Self(Some(GitInner { state, statuses }))
+        let web_url = get_web_url(&repo);
+        Self(Some(GitInner {
+            state,
+            statuses,
+            web_url,
+        }))
     }
 }
 
@@ -199,12 +237,19 @@ impl Segment for Git {
             State::Detached(ref short_id) => {
                 w.set_bg(Color::from_rgb(0, 0, 180))?;
                 w.set_fg(Color::from_rgb(230, 230, 230))?;
-                write!(w, " 📤 {} ", String::from_utf8_lossy(&*short_id))?;
+                write!(w, " 📤 {} ", String::from_utf8_lossy(short_id))?;
             }
             &State::OnBranch { upstream, ref name } => {
                 w.set_bg(Color::from_rgb(30, 180, 30))?;
                 w.set_fg(Color::from_rgb(0, 0, 0))?;
+                if let Some(ref web_url) = inner.web_url {
+                    let branch = super::percent_encode_path(name.as_bytes());
+                    w.start_link(&format!("{}/tree/{}", web_url, branch))?;
+                }
                 write!(w, " ⭠ {} ", name)?;
+                if inner.web_url.is_some() {
+                    w.end_link()?;
+                }
                 if let Some(Upstream { ahead, behind }) = upstream {
                     if behind > 0 {
                         w.start_segment(Color::from_rgb(120, 30, 30))?;
@@ -1,16 +1,206 @@
+use super::cache::Cache;
 use crate::{Color, ColoredStream, Segment};
-use std::{env, ffi::OsString, io::Write, path::Path};
+use std::{
+    env,
+    ffi::{CStr, CString, OsStr, OsString},
+    fs,
+    io::Write,
+    mem,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::Path,
+    ptr,
+    time::Duration,
+};
+
+const FQDN_MAX_AGE: Duration = Duration::from_secs(60 * 60);
 
 pub struct Hostname {
     hostname: OsString,
-    ssh: bool,
+    remote: Option<Remote>,
+    show_client: bool,
+    hash_color: Option<Color>,
+}
+
+#[derive(Clone, Copy)]
+enum RemoteKind {
+    Ssh,
+    Mosh,
+}
+
+struct Remote {
+    kind: RemoteKind,
+    /// Address the session comes from, if known
+    client: Option<String>,
+}
+
+/// First field of `SSH_CONNECTION`/`SSH_CLIENT`: `<client ip> <client port> ...`
+fn ssh_client(value: &[u8]) -> Option<String> {
+    let ip = value.split(|&b| b == b' ').next()?;
+    std::str::from_utf8(ip)
+        .ok()
+        .filter(|ip| !ip.is_empty())
+        .map(str::to_string)
+}
+
+/// `comm` and parent of a process from `/proc/<pid>/stat`
+fn proc_parent(pid: libc::pid_t) -> Option<(Vec<u8>, libc::pid_t)> {
+    let stat = fs::read(format!("/proc/{}/stat", pid)).ok()?;
+    // `<pid> (<comm>) <state> <ppid> ...`, comm can contain anything, even `)`
+    let open = stat.iter().position(|&b| b == b'(')?;
+    let close = stat.iter().rposition(|&b| b == b')')?;
+    let comm = stat.get(open + 1..close)?.to_vec();
+    let rest = std::str::from_utf8(stat.get(close + 1..)?).ok()?;
+    let ppid = rest.split_whitespace().nth(1)?.parse().ok()?;
+    Some((comm, ppid))
+}
+
+/// `SSH_CONNECTION` from the environment of another process, readable for our own
+/// processes and, after `sudo`, for the user's
+fn proc_ssh_client(pid: libc::pid_t) -> Option<String> {
+    let environ = fs::read(format!("/proc/{}/environ", pid)).ok()?;
+    environ.split(|&b| b == 0).find_map(|var| {
+        var.strip_prefix(b"SSH_CONNECTION=")
+            .or_else(|| var.strip_prefix(b"SSH_CLIENT="))
+            .and_then(ssh_client)
+    })
+}
+
+/// Look for the SSH variables first, they are dropped by `sudo` and `su -` and not set
+/// by mosh, so also walk up the process tree looking for `sshd` or `mosh-server`
+fn detect_remote() -> Option<Remote> {
+    let ssh_var = env::var_os("SSH_CONNECTION").or_else(|| env::var_os("SSH_CLIENT"));
+    if ssh_var.is_some() || env::var_os("SSH_TTY").is_some() {
+        return Some(Remote {
+            kind: RemoteKind::Ssh,
+            client: ssh_var.and_then(|value| ssh_client(value.as_bytes())),
+        });
+    }
+    let mut client = None;
+    let mut pid = unsafe { libc::getppid() };
+    // bounded, in case /proc is weird
+    for _ in 0..64 {
+        if pid <= 1 {
+            break;
+        }
+        let (comm, ppid) = proc_parent(pid)?;
+        let kind = match &comm[..] {
+            // OpenSSH 9.8 runs sessions as `sshd-session`, socket activation leaves no `sshd`
+            b"sshd" | b"sshd-session" => Some(RemoteKind::Ssh),
+            b"mosh-server" => Some(RemoteKind::Mosh),
+            _ => None,
+        };
+        if client.is_none() {
+            client = proc_ssh_client(pid);
+        }
+        if let Some(kind) = kind {
+            return Some(Remote { kind, client });
+        }
+        pid = ppid;
+    }
+    None
+}
+
+/// Canonical name of `host` as resolved by the system
+fn fqdn(host: &OsStr) -> Option<OsString> {
+    let host = CString::new(host.as_bytes()).ok()?;
+    let mut hints: libc::addrinfo = unsafe { mem::zeroed() };
+    hints.ai_family = libc::AF_UNSPEC;
+    hints.ai_flags = libc::AI_CANONNAME;
+    let mut addrinfo = ptr::null_mut();
+    if unsafe { libc::getaddrinfo(host.as_ptr(), ptr::null(), &hints, &mut addrinfo) } != 0 {
+        return None;
+    }
+    let name = unsafe {
+        let canonname = (*addrinfo).ai_canonname;
+        let name = if canonname.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(canonname).to_bytes().to_vec())
+        };
+        libc::freeaddrinfo(addrinfo);
+        name
+    };
+    name.map(OsString::from_vec)
+}
+
+/// [`fqdn`], which blocks until the resolver answers or times out, remembered for a while
+/// so only one prompt stalls when DNS is slow or unreachable
+fn cached_fqdn(host: &OsStr) -> Option<OsString> {
+    let cache = host.to_str().and_then(|host| {
+        let files = [Path::new("/etc/hosts"), Path::new("/etc/resolv.conf")];
+        Cache::new("hostname", &format!("fqdn:{}", host), &files)
+    });
+    let cache = cache.map(|cache| cache.max_age(FQDN_MAX_AGE));
+    if let Some(cached) = cache.as_ref().and_then(Cache::get::<Option<String>>) {
+        return cached.map(OsString::from);
+    }
+    let fqdn = fqdn(host);
+    if let Some(cache) = cache {
+        // failures too, the next prompt would only wait for the same timeout
+        cache.put(&fqdn.as_ref().and_then(|fqdn| fqdn.to_str()));
+    }
+    fqdn
+}
+
+/// The hostname as selected by `POWERLINE_HOSTNAME_MODE` (`short` or `fqdn`, as reported
+/// by the system otherwise), minus the first matching suffix of `POWERLINE_HOSTNAME_STRIP`
+fn display_name(hostname: OsString) -> OsString {
+    let mut name = match env::var("POWERLINE_HOSTNAME_MODE").as_deref() {
+        Ok("short") => hostname
+            .as_bytes()
+            .split(|&b| b == b'.')
+            .next()
+            .map(|short| OsStr::from_bytes(short).to_owned())
+            .unwrap_or(hostname),
+        Ok("fqdn") => cached_fqdn(&hostname).unwrap_or(hostname),
+        _ => hostname,
+    }
+    .into_vec();
+    let strip = env::var("POWERLINE_HOSTNAME_STRIP").unwrap_or_default();
+    let suffix = strip
+        .split(',')
+        .filter(|suffix| !suffix.is_empty())
+        .find(|suffix| name.len() > suffix.len() && name.ends_with(suffix.as_bytes()));
+    if let Some(suffix) = suffix {
+        name.truncate(name.len() - suffix.len());
+    }
+    OsString::from_vec(name)
+}
+
+/// A color unique to `name` that is the same in every session
+fn hash_color(name: &[u8]) -> Color {
+    let hash = super::fnv1a(name);
+    let hue = (hash % 360) as f32;
+    // keep it dark enough for white text
+    let (saturation, value) = (0.7, 0.55);
+    let c = value * saturation;
+    let x = c * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = value - c;
+    let channel = |v: f32| ((v + m) * 255.0).round() as u8;
+    Color::from_rgb(channel(r), channel(g), channel(b))
 }
 
 impl Hostname {
     pub fn new() -> Self {
+        let hostname = hostname::get().unwrap_or_default();
+        // hash the name as reported by the system, so the color doesn't depend on the mode
+        let hash_color = match env::var("POWERLINE_HOSTNAME_COLOR").as_deref() {
+            Ok("hash") if !hostname.is_empty() => Some(hash_color(hostname.as_bytes())),
+            _ => None,
+        };
         Self {
-            hostname: hostname::get().unwrap_or(OsString::new()),
-            ssh: env::var_os("SSH_CLIENT").is_some(),
+            hostname: display_name(hostname),
+            remote: detect_remote(),
+            show_client: super::env_flag("POWERLINE_HOSTNAME_CLIENT"),
+            hash_color,
         }
     }
 }
@@ -19,16 +209,30 @@ impl Segment for Hostname {
     fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()> {
         let bg = if self.hostname.is_empty() {
             Color::from_rgb(255, 0, 0)
-        } else if self.ssh {
+        } else if let Some(color) = self.hash_color {
+            color
+        } else if self.remote.is_some() {
             Color::from_rgb(255, 80, 0)
         } else {
             Color::from_rgb(30, 30, 30)
         };
         w.set_bg(bg)?;
         w.set_fg(Color::from_rgb(255, 255, 255))?;
-        // HACK: make `Path` from `OsStr` so we can call `.display()`
-        let icon = if self.ssh { '🔐' } else { '💻' };
-        write!(w, " {} {} ", icon, Path::new(&self.hostname).display())?;
+        let icon = match self.remote.as_ref().map(|remote| remote.kind) {
+            Some(RemoteKind::Ssh) => '🔐',
+            Some(RemoteKind::Mosh) => '📡',
+            None => '💻',
+        };
+        write!(w, " {} ", icon)?;
+        w.write_all(self.hostname.as_bytes())?;
+        write!(w, " ")?;
+        let client = self
+            .remote
+            .as_ref()
+            .and_then(|remote| remote.client.as_ref());
+        if let (true, Some(client)) = (self.show_client, client) {
+            write!(w, "← {} ", client)?;
+        }
         Ok(())
     }
 }
@@ -0,0 +1,37 @@
+use crate::{Color, ColoredStream, Segment};
+use std::io::Write;
+
+/// Background jobs of the shell, as counted by the shell hook
+pub struct Jobs {
+    running: u32,
+    stopped: u32,
+}
+
+impl Jobs {
+    pub fn new(running: Option<u32>, stopped: Option<u32>) -> Self {
+        Self {
+            running: running.unwrap_or(0),
+            stopped: stopped.unwrap_or(0),
+        }
+    }
+}
+
+impl Segment for Jobs {
+    fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()> {
+        if self.running == 0 && self.stopped == 0 {
+            return Ok(());
+        }
+        w.set_bg(Color::from_rgb(40, 60, 120))?;
+        w.set_fg(Color::from_rgb(220, 220, 220))?;
+        write!(w, " ")?;
+        if self.running > 0 {
+            write!(w, "⚙ {} ", self.running)?;
+        }
+        if self.stopped > 0 {
+            // suspended jobs are the ones that get forgotten
+            w.set_fg(Color::from_rgb(255, 200, 0))?;
+            write!(w, "⏸ {} ", self.stopped)?;
+        }
+        Ok(())
+    }
+}
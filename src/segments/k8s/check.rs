@@ -0,0 +1,267 @@
+use super::kubeconfig::{self, AuthInfo};
+use chrono::{DateTime, TimeZone, Utc};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+pub struct Problem {
+    pub severity: Severity,
+    message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning: {}", self.message),
+            Severity::Error => write!(f, "error: {}", self.message),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Problems(Vec<Problem>);
+
+impl Problems {
+    fn warning(&mut self, message: String) {
+        self.0.push(Problem {
+            severity: Severity::Warning,
+            message,
+        });
+    }
+    fn error(&mut self, message: String) {
+        self.0.push(Problem {
+            severity: Severity::Error,
+            message,
+        });
+    }
+}
+
+/// Load and merge `paths` the same way kubectl does and report everything suspicious
+///
+/// Files that can't be loaded are reported and left out of the merge.
+pub fn check(paths: &[PathBuf]) -> Vec<Problem> {
+    let mut problems = Problems::default();
+    let loaded = kubeconfig::load(paths);
+    for (path, e) in &loaded.errors {
+        problems.error(format!("{:?}: {}", path, e));
+    }
+    let mut defined_in = HashMap::new();
+    for (path, config) in &loaded.files {
+        let names = config
+            .clusters
+            .iter()
+            .map(|c| ("cluster", &c.name))
+            .chain(config.auth_infos.iter().map(|u| ("user", &u.name)))
+            .chain(config.contexts.iter().map(|c| ("context", &c.name)));
+        for (kind, name) in names {
+            match defined_in.entry((kind, name.clone())) {
+                Entry::Occupied(first) => problems.warning(format!(
+                    "{} {:?} in {:?} is shadowed by the one in {:?}",
+                    kind,
+                    name,
+                    path,
+                    first.get()
+                )),
+                Entry::Vacant(entry) => {
+                    entry.insert(path);
+                }
+            }
+        }
+    }
+    let config = match loaded.merged {
+        Some(config) => config,
+        None => {
+            problems.warning("no kubeconfig file found".to_string());
+            return problems.0;
+        }
+    };
+
+    match config.current_context {
+        None => problems.warning("current-context is not set".to_string()),
+        Some(ref name) if !config.contexts.iter().any(|c| &c.name == name) => {
+            problems.error(format!("current-context {:?} does not exist", name))
+        }
+        Some(_) => {}
+    }
+
+    for named in &config.contexts {
+        let context = &named.context;
+        if !config.clusters.iter().any(|c| c.name == context.cluster) {
+            problems.error(format!(
+                "context {:?} references missing cluster {:?}",
+                named.name, context.cluster
+            ));
+        }
+        if !config.auth_infos.iter().any(|u| u.name == context.user) {
+            problems.error(format!(
+                "context {:?} references missing user {:?}",
+                named.name, context.user
+            ));
+        }
+    }
+
+    for named in &config.clusters {
+        let cluster = &named.cluster;
+        if cluster.insecure_skip_tls_verify == Some(true) {
+            problems.warning(format!("cluster {:?} skips TLS verification", named.name));
+        }
+        if cluster.certificate_authority_data.is_none() {
+            if let Some(ref file) = cluster.certificate_authority {
+                check_file(
+                    &mut problems,
+                    "cluster",
+                    &named.name,
+                    "certificate-authority",
+                    file,
+                );
+            }
+        }
+    }
+
+    let now = Utc::now();
+    for named in &config.auth_infos {
+        let user = &named.auth_info;
+        if user.client_certificate_data.is_none() {
+            if let Some(ref file) = user.client_certificate {
+                check_file(
+                    &mut problems,
+                    "user",
+                    &named.name,
+                    "client-certificate",
+                    file,
+                );
+            }
+        }
+        if user.client_key_data.is_none() {
+            if let Some(ref file) = user.client_key {
+                check_file(&mut problems, "user", &named.name, "client-key", file);
+            }
+        }
+        if user.token.is_none() {
+            if let Some(ref file) = user.token_file {
+                check_file(&mut problems, "user", &named.name, "tokenFile", file);
+            }
+        }
+        for (what, expiry) in credential_expiry(user) {
+            if expiry <= now {
+                problems.error(format!(
+                    "user {:?} has {} that expired at {}",
+                    named.name, what, expiry
+                ));
+            }
+        }
+    }
+
+    problems.0
+}
+
+fn check_file(problems: &mut Problems, kind: &str, name: &str, field: &str, file: &str) {
+    if !Path::new(file).is_file() {
+        problems.error(format!(
+            "{} {:?} references missing {} {:?}",
+            kind, name, field, file
+        ));
+    }
+}
+
+/// Expiry of every credential of `user` that can be determined locally
+fn credential_expiry(user: &AuthInfo) -> Vec<(&'static str, DateTime<Utc>)> {
+    let mut expiry = vec![];
+    let certificate = match user.client_certificate_data {
+        Some(ref data) => base64::decode(data.trim()).ok(),
+        None => user
+            .client_certificate
+            .as_ref()
+            .and_then(|file| fs::read(file).ok()),
+    };
+    if let Some(not_after) = certificate
+        .and_then(|pem| pem_to_der(&pem))
+        .and_then(|der| certificate_not_after(&der))
+    {
+        expiry.push(("a client certificate", not_after));
+    }
+    if let Some(exp) = user.token.as_deref().and_then(jwt_expiry) {
+        expiry.push(("a token", exp));
+    }
+    if let Some(exp) = user
+        .auth_provider
+        .as_ref()
+        .and_then(|provider| provider.config.get("expiry"))
+        .and_then(|expiry| DateTime::parse_from_rfc3339(expiry).ok())
+    {
+        expiry.push(("an auth-provider token", exp.with_timezone(&Utc)));
+    }
+    expiry
+}
+
+/// `exp` claim of a JWT bearer token
+fn jwt_expiry(token: &str) -> Option<DateTime<Utc>> {
+    let payload = token.split('.').nth(1)?;
+    let payload = base64::decode_config(payload, base64::URL_SAFE_NO_PAD).ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&payload).ok()?;
+    let exp = claims.get("exp")?.as_i64()?;
+    Utc.timestamp_opt(exp, 0).single()
+}
+
+/// First certificate of a PEM bundle
+fn pem_to_der(pem: &[u8]) -> Option<Vec<u8>> {
+    let pem = std::str::from_utf8(pem).ok()?;
+    let body = pem
+        .split("-----BEGIN CERTIFICATE-----")
+        .nth(1)?
+        .split("-----END CERTIFICATE-----")
+        .next()?;
+    let body = body.split_whitespace().collect::<String>();
+    base64::decode(body).ok()
+}
+
+/// Split a DER encoded value into its tag, contents and the rest of the input
+fn der_next(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&len, rest) = rest.split_first()?;
+    let (len, rest) = if len & 0x80 == 0 {
+        (len as usize, rest)
+    } else {
+        let octets = (len & 0x7f) as usize;
+        if octets > 4 || rest.len() < octets {
+            return None;
+        }
+        let (len, rest) = rest.split_at(octets);
+        (len.iter().fold(0, |acc, &b| acc << 8 | b as usize), rest)
+    };
+    if rest.len() < len {
+        return None;
+    }
+    let (contents, rest) = rest.split_at(len);
+    Some((tag, contents, rest))
+}
+
+/// `notAfter` of an X.509 certificate, see RFC 5280 section 4.1
+fn certificate_not_after(der: &[u8]) -> Option<DateTime<Utc>> {
+    let (_, certificate, _) = der_next(der)?;
+    let (_, tbs_certificate, _) = der_next(certificate)?;
+    let (tag, _, mut rest) = der_next(tbs_certificate)?;
+    if tag == 0xa0 {
+        // explicit version, followed by the serial number
+        rest = der_next(rest)?.2;
+    }
+    let (_, _, rest) = der_next(rest)?; // signature
+    let (_, _, rest) = der_next(rest)?; // issuer
+    let (_, validity, _) = der_next(rest)?;
+    let (_, _, validity) = der_next(validity)?; // notBefore
+    let (tag, not_after, _) = der_next(validity)?;
+    let not_after = std::str::from_utf8(not_after).ok()?;
+    let format = match tag {
+        0x17 => "%y%m%d%H%M%SZ", // UTCTime
+        0x18 => "%Y%m%d%H%M%SZ", // GeneralizedTime
+        _ => return None,
+    };
+    Utc.datetime_from_str(not_after, format).ok()
+}
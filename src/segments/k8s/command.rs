@@ -0,0 +1,117 @@
+use super::{check, context_override, kubeconfig, ConfigError};
+
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "kube")]
+/// inspect and modify the kubeconfig
+pub struct KubeCommand {
+    #[argh(subcommand)]
+    cmd: KubeSubcommand,
+}
+
+#[derive(argh::FromArgs)]
+#[argh(subcommand)]
+enum KubeSubcommand {
+    Contexts(Contexts),
+    Use(Use),
+    Namespace(Namespace),
+    Check(Check),
+}
+
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "contexts")]
+/// list contexts, marking the current one with `*` (or `POWERLINE_KUBE_CONTEXT` if set)
+struct Contexts {}
+
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "use")]
+/// switch `current-context`
+struct Use {
+    #[argh(positional)]
+    /// name of the context to switch to
+    context: String,
+}
+
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "namespace")]
+/// set the namespace of the current context (or `POWERLINE_KUBE_CONTEXT` if set)
+struct Namespace {
+    #[argh(positional)]
+    /// namespace to use, the context's namespace is removed if omitted
+    namespace: Option<String>,
+}
+
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "check")]
+/// report problems in the merged kubeconfig
+struct Check {}
+
+impl KubeCommand {
+    pub fn run(self) -> kubeconfig::Result<()> {
+        if let KubeSubcommand::Check(Check {}) = self.cmd {
+            let problems = check::check(&kubeconfig::kubeconfig_paths());
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            let errors = problems
+                .iter()
+                .filter(|problem| matches!(problem.severity, check::Severity::Error))
+                .count();
+            if errors > 0 {
+                return Err(ConfigError::CheckFailed { errors });
+            }
+            return Ok(());
+        }
+        let (mut files, config) = kubeconfig::load_merged(&kubeconfig::kubeconfig_paths())?;
+        let context_override = context_override();
+        let current_context = context_override
+            .clone()
+            .or_else(|| config.current_context.clone());
+        // like kubectl, change the file that the merged value comes from
+        let index = match self.cmd {
+            KubeSubcommand::Contexts(Contexts {}) => {
+                for named_context in &config.contexts {
+                    let marker = if current_context.as_ref() == Some(&named_context.name) {
+                        '*'
+                    } else {
+                        ' '
+                    };
+                    println!("{} {}", marker, named_context.name);
+                }
+                return Ok(());
+            }
+            KubeSubcommand::Use(Use { context }) => {
+                if !config.contexts.iter().any(|c| c.name == context) {
+                    return Err(ConfigError::ContextNotFound {
+                        context_name: context,
+                    });
+                }
+                if context_override.is_some() {
+                    eprintln!("POWERLINE_KUBE_CONTEXT is set, this shell keeps using it");
+                }
+                let index = files
+                    .iter()
+                    .position(|(_, file)| file.current_context.is_some())
+                    .unwrap_or(0);
+                files[index].1.current_context = Some(context);
+                index
+            }
+            KubeSubcommand::Namespace(Namespace { namespace }) => {
+                let context_name = current_context.ok_or(ConfigError::CurrentContextNotSet)?;
+                let (index, named_context) = files
+                    .iter_mut()
+                    .enumerate()
+                    .find_map(|(index, (_, file))| {
+                        let named_context =
+                            file.contexts.iter_mut().find(|c| c.name == context_name)?;
+                        Some((index, named_context))
+                    })
+                    .ok_or(ConfigError::LoadContext { context_name })?;
+                named_context.context.namespace = namespace;
+                index
+            }
+            KubeSubcommand::Check(Check {}) => unreachable!(),
+        };
+        let (ref path, ref file) = files[index];
+        file.write_to(path)
+    }
+}
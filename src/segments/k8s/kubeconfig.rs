@@ -2,7 +2,14 @@ use super::ConfigError;
 
 // use crate::{config::utils, error::ConfigError, Result};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, path::Path};
+use serde_yaml::Mapping;
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
 
 pub type Result<T> = std::result::Result<T, ConfigError>;
 
@@ -15,6 +22,8 @@ pub type Result<T> = std::result::Result<T, ConfigError>;
 ///
 /// [`Config`][crate::Config] is the __intended__ developer interface to help create a [`Client`][crate::Client],
 /// and this will handle the difference between in-cluster deployment and local development.
+///
+/// Every type keeps the fields it doesn't model in `other`, so writing a file back doesn't drop them.
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct Kubeconfig {
     /// General information to be use for cli interactions
@@ -41,6 +50,8 @@ pub struct Kubeconfig {
     #[serde(rename = "apiVersion")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_version: Option<String>,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// Preferences stores extensions for cli.
@@ -50,6 +61,8 @@ pub struct Preferences {
     pub colors: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extensions: Option<Vec<NamedExtension>>,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// NamedExtention associates name with extension.
@@ -57,6 +70,8 @@ pub struct Preferences {
 pub struct NamedExtension {
     pub name: String,
     pub extension: serde_json::Value,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// NamedCluster associates name with cluster.
@@ -64,6 +79,8 @@ pub struct NamedExtension {
 pub struct NamedCluster {
     pub name: String,
     pub cluster: Cluster,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// Cluster stores information to connect Kubernetes cluster.
@@ -89,6 +106,8 @@ pub struct Cluster {
     /// Additional information for extenders so that reads and writes don't clobber unknown fields
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extensions: Option<Vec<NamedExtension>>,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// NamedAuthInfo associates name with authentication.
@@ -97,6 +116,8 @@ pub struct NamedAuthInfo {
     pub name: String,
     #[serde(rename = "user")]
     pub auth_info: AuthInfo,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// AuthInfo stores information to tell cluster who you are.
@@ -152,6 +173,8 @@ pub struct AuthInfo {
     /// Specifies a custom exec-based authentication plugin for the kubernetes cluster.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exec: Option<ExecConfig>,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// AuthProviderConfig stores auth for specified cloud provider.
@@ -159,6 +182,8 @@ pub struct AuthInfo {
 pub struct AuthProviderConfig {
     pub name: String,
     pub config: HashMap<String, String>,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// ExecConfig stores credential-plugin configuration.
@@ -180,6 +205,8 @@ pub struct ExecConfig {
     /// TODO: These are unioned with the host's environment, as well as variables client-go uses to pass argument to the plugin.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub env: Option<Vec<HashMap<String, String>>>,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// NamedContext associates name with context.
@@ -187,6 +214,8 @@ pub struct ExecConfig {
 pub struct NamedContext {
     pub name: String,
     pub context: Context,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// Context stores tuple of cluster and user information.
@@ -202,62 +231,89 @@ pub struct Context {
     /// Additional information for extenders so that reads and writes don't clobber unknown fields
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extensions: Option<Vec<NamedExtension>>,
+    #[serde(flatten)]
+    pub other: Mapping,
 }
 
 /// Some helpers on the raw Config object are exposed for people needing to parse it
 impl Kubeconfig {
-    /// Read a Config from an arbitrary location
-    pub fn read_from<P: AsRef<Path>>(path: P) -> Result<Kubeconfig> {
-        let data = fs::read_to_string(&path).map_err(|source| ConfigError::ReadFile {
-            path: path.as_ref().into(),
-            source,
-        })?;
-        // support multiple documents
-        let mut documents: Vec<Kubeconfig> = vec![];
-        for doc in serde_yaml::Deserializer::from_str(&data) {
-            let value = serde_yaml::Value::deserialize(doc).map_err(ConfigError::ParseYaml)?;
-            let kconf = serde_yaml::from_value(value).map_err(ConfigError::ParseYaml)?;
-            documents.push(kconf)
+    /// Resolve relative file references against `dir`, the directory of the file they're in
+    fn make_paths_absolute(&mut self, dir: &Path) {
+        for named in self.clusters.iter_mut() {
+            if let Some(path) = &named.cluster.certificate_authority {
+                if let Some(abs_path) = to_absolute(dir, path) {
+                    named.cluster.certificate_authority = Some(abs_path);
+                }
+            }
         }
-
-        // Remap all files we read to absolute paths.
-        let mut merged_docs = None;
-        for mut config in documents {
-            if let Some(dir) = path.as_ref().parent() {
-                for named in config.clusters.iter_mut() {
-                    if let Some(path) = &named.cluster.certificate_authority {
-                        if let Some(abs_path) = to_absolute(dir, path) {
-                            named.cluster.certificate_authority = Some(abs_path);
-                        }
-                    }
+        for named in self.auth_infos.iter_mut() {
+            if let Some(path) = &named.auth_info.client_certificate {
+                if let Some(abs_path) = to_absolute(dir, path) {
+                    named.auth_info.client_certificate = Some(abs_path);
                 }
-                for named in config.auth_infos.iter_mut() {
-                    if let Some(path) = &named.auth_info.client_certificate {
-                        if let Some(abs_path) = to_absolute(dir, path) {
-                            named.auth_info.client_certificate = Some(abs_path);
-                        }
-                    }
-                    if let Some(path) = &named.auth_info.client_key {
-                        if let Some(abs_path) = to_absolute(dir, path) {
-                            named.auth_info.client_key = Some(abs_path);
-                        }
-                    }
-                    if let Some(path) = &named.auth_info.token_file {
-                        if let Some(abs_path) = to_absolute(dir, path) {
-                            named.auth_info.token_file = Some(abs_path);
-                        }
-                    }
+            }
+            if let Some(path) = &named.auth_info.client_key {
+                if let Some(abs_path) = to_absolute(dir, path) {
+                    named.auth_info.client_key = Some(abs_path);
                 }
             }
+            if let Some(path) = &named.auth_info.token_file {
+                if let Some(abs_path) = to_absolute(dir, path) {
+                    named.auth_info.token_file = Some(abs_path);
+                }
+            }
+        }
+    }
+
+    /// Read a Config the way it is stored on disk, without remapping relative paths,
+    /// so it can be written back with [`Kubeconfig::write_to`]
+    pub fn read_raw<P: AsRef<Path>>(path: P) -> Result<Kubeconfig> {
+        let mut merged_docs = None;
+        for config in read_documents(path.as_ref())? {
             if let Some(c) = merged_docs {
                 merged_docs = Some(Kubeconfig::merge(c, config)?);
             } else {
                 merged_docs = Some(config);
             }
         }
-        let config =
-            merged_docs.ok_or_else(|| ConfigError::EmptyKubeconfig(path.as_ref().to_path_buf()))?;
-        Ok(config)
+        merged_docs.ok_or_else(|| ConfigError::EmptyKubeconfig(path.as_ref().to_path_buf()))
+    }
+
+    /// Write the Config to `path`, replacing the file atomically
+    ///
+    /// A symlinked `path` stays a symlink, the file it points to is replaced.
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let write_err = |source| ConfigError::WriteFile {
+            path: path.into(),
+            source,
+        };
+        let path = match fs::canonicalize(path) {
+            Ok(path) => path,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => path.to_path_buf(),
+            Err(e) => return Err(write_err(e)),
+        };
+        let data = serde_yaml::to_string(self).map_err(ConfigError::SerializeYaml)?;
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(format!(".{}.tmp", std::process::id()));
+        // credentials must never be readable by others, not even for a moment
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&tmp)
+            .map_err(write_err)?;
+        let written = file
+            .write_all(data.as_bytes())
+            .and_then(|()| match fs::metadata(&path) {
+                Ok(meta) => file.set_permissions(meta.permissions()),
+                Err(_) => Ok(()),
+            })
+            .and_then(|()| fs::rename(&tmp, &path));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        written.map_err(write_err)
     }
 
     /// Merge kubeconfig file according to the rules described in
@@ -272,15 +328,15 @@ impl Kubeconfig {
     /// >   Example: Preserve the context of the first file to set `current-context`.
     /// >   Example: If two files specify a `red-user`, use only values from the first file's `red-user`.
     /// >            Even if the second file has non-conflicting entries under `red-user`, discard them.
-    fn merge(mut self, next: Kubeconfig) -> Result<Self> {
+    pub fn merge(mut self, next: Kubeconfig) -> Result<Self> {
         if self.kind.is_some() && next.kind.is_some() && self.kind != next.kind {
-            return Err(ConfigError::KindMismatch.into());
+            return Err(ConfigError::KindMismatch);
         }
         if self.api_version.is_some()
             && next.api_version.is_some()
             && self.api_version != next.api_version
         {
-            return Err(ConfigError::ApiVersionMismatch.into());
+            return Err(ConfigError::ApiVersionMismatch);
         }
 
         self.kind = self.kind.or(next.kind);
@@ -291,17 +347,113 @@ impl Kubeconfig {
         append_new_named(&mut self.contexts, next.contexts, |x| &x.name);
         self.current_context = self.current_context.or(next.current_context);
         self.extensions = self.extensions.or(next.extensions);
+        for (key, value) in next.other {
+            if !self.other.contains_key(&key) {
+                self.other.insert(key, value);
+            }
+        }
         Ok(self)
     }
 }
 
+/// All files that make up the kubeconfig, the entries of `KUBECONFIG` or `~/.kube/config`
+pub fn kubeconfig_paths() -> Vec<PathBuf> {
+    match env::var_os("KUBECONFIG") {
+        Some(paths) => env::split_paths(&paths)
+            .filter(|path| !path.as_os_str().is_empty())
+            .collect(),
+        None => dirs::home_dir()
+            .map(|home| home.join(".kube").join("config"))
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// The kubeconfig files loaded by [`load`]
+pub struct Loaded {
+    /// Every file as it is stored on disk, so it can be written back
+    pub files: Vec<(PathBuf, Kubeconfig)>,
+    /// All of `files` merged, relative paths resolved against the file they're in
+    pub merged: Option<Kubeconfig>,
+    /// Files left out because they couldn't be read, parsed or merged
+    pub errors: Vec<(PathBuf, ConfigError)>,
+}
+
+/// Load `paths` and merge them the way kubectl does
+///
+/// Files that don't exist are skipped, as kubectl does, so `KUBECONFIG` can list files
+/// that are only there sometimes. Any other error leaves the file out and is collected.
+pub fn load(paths: &[PathBuf]) -> Loaded {
+    let mut loaded = Loaded {
+        files: vec![],
+        merged: None,
+        errors: vec![],
+    };
+    for path in paths {
+        let raw = match Kubeconfig::read_raw(path) {
+            Ok(raw) => raw,
+            Err(ConfigError::ReadFile { ref source, .. })
+                if source.kind() == io::ErrorKind::NotFound =>
+            {
+                continue
+            }
+            Err(e) => {
+                loaded.errors.push((path.clone(), e));
+                continue;
+            }
+        };
+        let mut config = raw.clone();
+        if let Some(dir) = path.parent() {
+            config.make_paths_absolute(dir);
+        }
+        loaded.merged = match loaded.merged {
+            Some(merged) => match merged.clone().merge(config) {
+                Ok(merged) => Some(merged),
+                Err(e) => {
+                    loaded.errors.push((path.clone(), e));
+                    loaded.merged = Some(merged);
+                    continue;
+                }
+            },
+            None => Some(config),
+        };
+        loaded.files.push((path.clone(), raw));
+    }
+    loaded
+}
+
+/// [`load`] where any error fails, as does not finding a single file
+pub fn load_merged(paths: &[PathBuf]) -> Result<(Vec<(PathBuf, Kubeconfig)>, Kubeconfig)> {
+    let loaded = load(paths);
+    if let Some((_, e)) = loaded.errors.into_iter().next() {
+        return Err(e);
+    }
+    let merged = loaded.merged.ok_or(ConfigError::KubeconfigNotFound)?;
+    Ok((loaded.files, merged))
+}
+
+fn read_documents(path: &Path) -> Result<Vec<Kubeconfig>> {
+    let data = fs::read_to_string(path).map_err(|source| ConfigError::ReadFile {
+        path: path.into(),
+        source,
+    })?;
+    // support multiple documents
+    let mut documents: Vec<Kubeconfig> = vec![];
+    for doc in serde_yaml::Deserializer::from_str(&data) {
+        let value = serde_yaml::Value::deserialize(doc).map_err(ConfigError::ParseYaml)?;
+        let kconf = serde_yaml::from_value(value).map_err(ConfigError::ParseYaml)?;
+        documents.push(kconf)
+    }
+    Ok(documents)
+}
+
 fn append_new_named<T, F>(base: &mut Vec<T>, next: Vec<T>, f: F)
 where
     F: Fn(&T) -> &String,
 {
     use std::collections::HashSet;
     base.extend({
-        let existing = base.iter().map(|x| f(x)).collect::<HashSet<_>>();
+        let existing = base.iter().map(&f).collect::<HashSet<_>>();
         next.into_iter()
             .filter(|x| !existing.contains(f(x)))
             .collect::<Vec<_>>()
@@ -26,9 +26,26 @@ pub enum ConfigError {
         source: std::io::Error,
     },
 
+    #[error("Failed to write '{path:?}': {source}")]
+    WriteFile {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
     #[error("Failed to parse Kubeconfig YAML: {0}")]
     ParseYaml(#[source] serde_yaml::Error),
 
     #[error("Failed to find a single YAML document in Kubeconfig: {0}")]
     EmptyKubeconfig(PathBuf),
+
+    #[error("Failed to serialize Kubeconfig YAML: {0}")]
+    SerializeYaml(#[source] serde_yaml::Error),
+
+    #[error("Failed to find a kubeconfig file")]
+    KubeconfigNotFound,
+    #[error("No context exists with the name: {context_name}")]
+    ContextNotFound { context_name: String },
+    #[error("Found {errors} error(s) in kubeconfig")]
+    CheckFailed { errors: usize },
 }
@@ -1,30 +1,66 @@
+mod check;
+mod command;
 mod kubeconfig;
 mod kubeconfig_error;
 
+pub use command::KubeCommand;
+
+use super::cache;
 use crate::{Color, ColoredStream, Segment};
 use kubeconfig_error::ConfigError;
-use std::{env, io::Write, path::Path};
+use std::{env, fs, io::Write, path::PathBuf};
+
+const SERVICEACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
 
 pub struct K8s {
     server_name: Option<kubeconfig::Result<String>>,
     mgr_name: Option<kubeconfig::Result<String>>,
+    in_cluster_namespace: Option<String>,
+    context_override: Option<String>,
+}
+
+/// Per-shell context set through `POWERLINE_KUBE_CONTEXT`, takes precedence over `current-context`
+fn context_override() -> Option<String> {
+    env::var("POWERLINE_KUBE_CONTEXT")
+        .ok()
+        .filter(|context| !context.is_empty())
+}
+
+fn servername_from_kubeconfig(
+    paths: &[PathBuf],
+    context: Option<&str>,
+) -> kubeconfig::Result<String> {
+    let stamped = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+    let cache = cache::Cache::new(
+        "kube",
+        &format!("server:{}", context.unwrap_or("")),
+        &stamped,
+    );
+    if let Some(server_name) = cache.as_ref().and_then(cache::Cache::get) {
+        return Ok(server_name);
+    }
+    let server_name = read_servername(paths, context)?;
+    if let Some(cache) = cache {
+        cache.put(&server_name);
+    }
+    Ok(server_name)
 }
 
-fn servername_from_kubeconfig<P: AsRef<Path>>(path: P) -> kubeconfig::Result<String> {
-    let config = kubeconfig::Kubeconfig::read_from(path)?;
+fn read_servername(paths: &[PathBuf], context: Option<&str>) -> kubeconfig::Result<String> {
+    let (_, config) = kubeconfig::load_merged(paths)?;
 
-    let context_name = if let Some(name) = &config.current_context {
+    let context_name = if let Some(name) = context.or(config.current_context.as_deref()) {
         name
     } else {
-        return Err(ConfigError::CurrentContextNotSet.into());
+        return Err(ConfigError::CurrentContextNotSet);
     };
     let current_context = config
         .contexts
         .iter()
-        .find(|named_context| &named_context.name == context_name)
+        .find(|named_context| named_context.name == context_name)
         .map(|named_context| &named_context.context)
         .ok_or_else(|| ConfigError::LoadContext {
-            context_name: context_name.clone(),
+            context_name: context_name.to_string(),
         })?;
 
     let cluster_name = &current_context.cluster;
@@ -56,10 +92,26 @@ fn servername_from_kubeconfig<P: AsRef<Path>>(path: P) -> kubeconfig::Result<Str
     Ok(cluster.server.clone())
 }
 
+/// Namespace of the pod's service account when running inside a cluster, empty if
+/// the namespace isn't mounted
+///
+/// The directory can be overridden with `POWERLINE_KUBE_SERVICEACCOUNT_DIR`.
+fn in_cluster_namespace() -> Option<String> {
+    env::var_os("KUBERNETES_SERVICE_HOST")?;
+    let dir = env::var_os("POWERLINE_KUBE_SERVICEACCOUNT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(SERVICEACCOUNT_DIR));
+    if !dir.join("token").is_file() {
+        return None;
+    }
+    let namespace = fs::read_to_string(dir.join("namespace")).unwrap_or_default();
+    Some(namespace.trim().to_string())
+}
+
 impl K8s {
     pub fn new() -> Self {
         let mgr_name = std::env::var_os("MGR_KUBECONFIG").map(|path| {
-            let mgr = servername_from_kubeconfig(path)?;
+            let mgr = servername_from_kubeconfig(&[PathBuf::from(path)], None)?;
             Ok(mgr
                 .trim()
                 .strip_prefix("https://kube.")
@@ -69,20 +121,24 @@ impl K8s {
                 })
                 .unwrap_or(mgr))
         });
-        let server_name = if let Some(kubeconfig_path) = env::var_os("KUBECONFIG") {
-            Some(servername_from_kubeconfig(kubeconfig_path))
+        let context_override = context_override();
+        let paths = kubeconfig::kubeconfig_paths();
+        let server_name = match servername_from_kubeconfig(&paths, context_override.as_deref()) {
+            // without an explicit kubeconfig, not having one is fine; a pinned context that
+            // doesn't resolve is not
+            Err(_) if env::var_os("KUBECONFIG").is_none() && context_override.is_none() => None,
+            server_name => Some(server_name),
+        };
+        let in_cluster_namespace = if server_name.is_none() {
+            in_cluster_namespace()
         } else {
-            dirs::home_dir()
-                .and_then(|home| {
-                    servername_from_kubeconfig(home.join(".kube").join("config"))
-                        .ok()
-                        .map(|server_name| Some(Ok(server_name)))
-                })
-                .unwrap_or(None)
+            None
         };
         Self {
             server_name,
             mgr_name,
+            in_cluster_namespace,
+            context_override,
         }
     }
 }
@@ -91,6 +147,11 @@ impl Segment for K8s {
     fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()> {
         if let Some(ref server_name) = self.server_name {
             match server_name {
+                Ok(server_name) if self.context_override.is_some() => {
+                    w.set_bg(Color::from_rgb(120, 10, 200))?;
+                    w.set_fg(Color::from_rgb(230, 230, 230))?;
+                    write!(w, " ☸  📌 {} ", server_name)?;
+                }
                 Ok(server_name) => {
                     w.set_bg(Color::from_rgb(10, 10, 200))?;
                     w.set_fg(Color::from_rgb(230, 230, 230))?;
@@ -99,9 +160,20 @@ impl Segment for K8s {
                 Err(_) => {
                     w.set_bg(Color::from_rgb(255, 0, 0))?;
                     w.set_fg(Color::from_rgb(230, 230, 230))?;
-                    write!(w, " ☠ ")?;
+                    match self.context_override {
+                        Some(ref context) => write!(w, " ☠ 📌 {} ", context)?,
+                        None => write!(w, " ☠ ")?,
+                    }
                 }
             }
+        } else if let Some(ref namespace) = self.in_cluster_namespace {
+            w.set_bg(Color::from_rgb(10, 10, 200))?;
+            w.set_fg(Color::from_rgb(230, 230, 230))?;
+            if namespace.is_empty() {
+                write!(w, " ☸  in-cluster ")?;
+            } else {
+                write!(w, " ☸  in-cluster:{} ", namespace)?;
+            }
         }
         match self.mgr_name {
             Some(Ok(ref mgr_name)) => {
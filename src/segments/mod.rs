@@ -1,5 +1,8 @@
 use crate::{ColoredStream};
 
+mod alias;
+mod cache;
+mod template;
 
 mod time;
 pub use time::Time;
@@ -7,9 +10,15 @@ pub use time::Time;
 mod hostname;
 pub use self::hostname::Hostname;
 
+mod container;
+pub use container::Container;
+
 mod user;
 pub use user::User;
 
+mod shell;
+pub use shell::Shell;
+
 mod cwd;
 pub use cwd::Cwd;
 
@@ -20,11 +29,41 @@ mod openstack;
 pub use openstack::Openstack;
 
 mod k8s;
-pub use k8s::K8s;
+pub use k8s::{K8s, KubeCommand};
 
 mod exitcode;
 pub use exitcode::ExitCode;
 
+mod duration;
+pub use duration::Duration;
+
+mod jobs;
+pub use jobs::Jobs;
+
+/// `true` if the environment variable is set to anything but empty or `0`
+fn env_flag(name: &str) -> bool {
+    std::env::var_os(name).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// FNV-1a hash of `bytes`, unlike `DefaultHasher` it is guaranteed to stay the same between builds
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5_u32, |hash, &b| {
+        (hash ^ b as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Percent-encode everything but unreserved characters and `/` for use in a URL path
+fn percent_encode_path(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut encoded, &byte| {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+        encoded
+    })
+}
+
 pub trait Segment {
     fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()>;
 }
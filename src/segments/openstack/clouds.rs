@@ -0,0 +1,159 @@
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, CloudsError>;
+
+#[derive(Error, Debug)]
+/// Possible errors when loading clouds.yaml
+pub enum CloudsError {
+    #[error("Failed to read '{path:?}': {source}")]
+    ReadFile {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse '{path:?}': {source}")]
+    ParseYaml {
+        path: PathBuf,
+        #[source]
+        source: serde_yaml::Error,
+    },
+
+    #[error("Cloud '{0}' is not defined in clouds.yaml")]
+    CloudNotFound(String),
+}
+
+#[derive(Deserialize, Default)]
+struct CloudsFile {
+    #[serde(default)]
+    clouds: HashMap<String, Cloud>,
+}
+
+/// A single entry of `clouds:`, only the fields we display
+///
+/// See <https://docs.openstack.org/openstacksdk/latest/user/config/configuration.html>
+#[derive(Deserialize, Default, Debug)]
+pub struct Cloud {
+    #[serde(default)]
+    pub auth: Auth,
+    pub region_name: Option<String>,
+    pub regions: Option<Vec<Region>>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+pub struct Auth {
+    pub auth_url: Option<String>,
+    pub project_name: Option<String>,
+    pub tenant_name: Option<String>,
+    pub project_id: Option<String>,
+    pub project_domain_name: Option<String>,
+    pub user_domain_name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Region {
+    Name(String),
+    Detailed { name: String },
+}
+
+impl Cloud {
+    pub fn project(&self) -> Option<&str> {
+        self.auth
+            .project_name
+            .as_deref()
+            .or(self.auth.tenant_name.as_deref())
+            .or(self.auth.project_id.as_deref())
+    }
+
+    /// `region_name`, or the first of `regions` which is what the client defaults to
+    pub fn region(&self) -> Option<&str> {
+        self.region_name.as_deref().or_else(|| {
+            self.regions
+                .as_ref()
+                .and_then(|regions| regions.first())
+                .map(|region| match region {
+                    Region::Name(name) | Region::Detailed { name } => name.as_str(),
+                })
+        })
+    }
+
+    /// Values from `secure` win, the same as the OpenStack client does
+    fn merge(self, secure: Cloud) -> Cloud {
+        Cloud {
+            auth: Auth {
+                auth_url: secure.auth.auth_url.or(self.auth.auth_url),
+                project_name: secure.auth.project_name.or(self.auth.project_name),
+                tenant_name: secure.auth.tenant_name.or(self.auth.tenant_name),
+                project_id: secure.auth.project_id.or(self.auth.project_id),
+                project_domain_name: secure
+                    .auth
+                    .project_domain_name
+                    .or(self.auth.project_domain_name),
+                user_domain_name: secure.auth.user_domain_name.or(self.auth.user_domain_name),
+            },
+            region_name: secure.region_name.or(self.region_name),
+            regions: secure.regions.or(self.regions),
+        }
+    }
+}
+
+/// `./`, `~/.config/openstack/` and `/etc/openstack/`, in the order they are searched
+fn config_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Ok(cwd) = env::current_dir() {
+        dirs.push(cwd);
+    }
+    if let Some(config) = dirs::config_dir() {
+        dirs.push(config.join("openstack"));
+    }
+    dirs.push(PathBuf::from("/etc/openstack"));
+    dirs
+}
+
+/// The file named in `env_var`, or the first `<name>.yaml`/`<name>.yml` found in [`config_dirs`]
+fn find_file(env_var: &str, name: &str) -> Option<PathBuf> {
+    if let Some(path) = env::var_os(env_var) {
+        return Some(PathBuf::from(path));
+    }
+    config_dirs().into_iter().find_map(|dir| {
+        ["yaml", "yml"]
+            .iter()
+            .map(|ext| dir.join(format!("{}.{}", name, ext)))
+            .find(|path| path.is_file())
+    })
+}
+
+fn read_clouds(path: &Path) -> Result<CloudsFile> {
+    let data = fs::read_to_string(path).map_err(|source| CloudsError::ReadFile {
+        path: path.into(),
+        source,
+    })?;
+    serde_yaml::from_str(&data).map_err(|source| CloudsError::ParseYaml {
+        path: path.into(),
+        source,
+    })
+}
+
+/// Load the cloud called `name` from clouds.yaml, merged with secure.yaml
+pub fn load(name: &str) -> Result<Cloud> {
+    let cloud = match find_file("OS_CLIENT_CONFIG_FILE", "clouds") {
+        Some(path) => read_clouds(&path)?.clouds.remove(name),
+        None => None,
+    };
+    let secure = match find_file("OS_CLIENT_SECURE_FILE", "secure") {
+        Some(path) => read_clouds(&path)?.clouds.remove(name),
+        None => None,
+    };
+    match (cloud, secure) {
+        (Some(cloud), Some(secure)) => Ok(cloud.merge(secure)),
+        (Some(cloud), None) | (None, Some(cloud)) => Ok(cloud),
+        (None, None) => Err(CloudsError::CloudNotFound(name.to_string())),
+    }
+}
@@ -0,0 +1,165 @@
+mod clouds;
+
+use chrono::{DateTime, TimeZone, Utc};
+use std::{env, fs, io::Write as _};
+
+use super::{alias::Aliases, template};
+use crate::{Color, ColoredStream, Segment};
+
+/// Used unless overridden with `POWERLINE_OPENSTACK_FORMAT`
+///
+/// Available placeholders are `{cloud}`, `{project}`, `{region}`, `{project_domain}`,
+/// `{user_domain}` and `{auth_host}`.
+const DEFAULT_FORMAT: &str = "[{cloud}:]{project}[@{region}]";
+
+pub struct Openstack {
+    cloud_name: Option<String>,
+    cloud: Option<clouds::Result<clouds::Cloud>>,
+    project_name: Option<String>,
+    region_name: Option<String>,
+    project_domain_name: Option<String>,
+    user_domain_name: Option<String>,
+    auth_host: Option<String>,
+    token_expires: Option<DateTime<Utc>>,
+    format: String,
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string_lossy().into_owned())
+}
+
+/// `https://keystone.example.com:5000/v3` -> `keystone.example.com`
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if host.starts_with('[') {
+        host.split_inclusive(']').next().unwrap_or(host)
+    } else {
+        host.split(':').next().unwrap_or(host)
+    }
+}
+
+/// Keystone and the `openstack` client use several formats, also accept a unix timestamp
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z"))
+        .map(|time| time.with_timezone(&Utc))
+        .ok()
+        .or_else(|| Utc.datetime_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok())
+        .or_else(|| Utc.timestamp_opt(value.parse().ok()?, 0).single())
+}
+
+/// Expiry from the output of `openstack token issue -f json` or a keystone token response
+fn token_file_expiry(path: &str) -> Option<DateTime<Utc>> {
+    let data = fs::read(path).ok()?;
+    let json: serde_json::Value = serde_json::from_slice(&data).ok()?;
+    let token = json.get("token").unwrap_or(&json);
+    token
+        .get("expires")
+        .or_else(|| token.get("expires_at"))
+        .and_then(serde_json::Value::as_str)
+        .and_then(parse_timestamp)
+}
+
+/// `1h05m`, `12m`, `45s`
+fn format_remaining(seconds: i64) -> String {
+    match seconds {
+        s if s >= 3600 => format!("{}h{:02}m", s / 3600, s % 3600 / 60),
+        s if s >= 60 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+impl Openstack {
+    pub fn new() -> Self {
+        let cloud_name = env_var("OS_CLOUD");
+        let cloud = cloud_name.as_deref().map(clouds::load);
+        let from_cloud = |f: fn(&clouds::Cloud) -> Option<&str>| match cloud {
+            Some(Ok(ref cloud)) => f(cloud).map(str::to_string),
+            _ => None,
+        };
+        // environment variables override clouds.yaml, as with the `openstack` client
+        let project_name = env_var("OS_PROJECT_NAME")
+            .or_else(|| env_var("OS_TENANT_NAME"))
+            .or_else(|| from_cloud(clouds::Cloud::project));
+        let region_name = env_var("OS_REGION_NAME").or_else(|| from_cloud(clouds::Cloud::region));
+        let project_domain_name = env_var("OS_PROJECT_DOMAIN_NAME")
+            .or_else(|| from_cloud(|cloud| cloud.auth.project_domain_name.as_deref()));
+        let user_domain_name = env_var("OS_USER_DOMAIN_NAME")
+            .or_else(|| from_cloud(|cloud| cloud.auth.user_domain_name.as_deref()));
+        let auth_host = env_var("OS_AUTH_URL")
+            .or_else(|| from_cloud(|cloud| cloud.auth.auth_url.as_deref()))
+            .map(|url| {
+                let host = url_host(&url);
+                Aliases::from_env("POWERLINE_OPENSTACK_ALIASES")
+                    .apply(host)
+                    .unwrap_or_else(|| host.to_string())
+            });
+        // `OS_TOKEN_EXPIRES` or a token saved to `POWERLINE_OPENSTACK_TOKEN_FILE`
+        let token_expires = env_var("OS_TOKEN_EXPIRES")
+            .and_then(|expires| parse_timestamp(&expires))
+            .or_else(|| {
+                env_var("POWERLINE_OPENSTACK_TOKEN_FILE").and_then(|path| token_file_expiry(&path))
+            });
+        Self {
+            cloud_name,
+            cloud,
+            project_name,
+            region_name,
+            project_domain_name,
+            user_domain_name,
+            auth_host,
+            token_expires,
+            format: env_var("POWERLINE_OPENSTACK_FORMAT")
+                .unwrap_or_else(|| DEFAULT_FORMAT.to_string()),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&str> {
+        match name {
+            "cloud" => self.cloud_name.as_deref(),
+            "project" => self.project_name.as_deref(),
+            "region" => self.region_name.as_deref(),
+            "project_domain" => self.project_domain_name.as_deref(),
+            "user_domain" => self.user_domain_name.as_deref(),
+            "auth_host" => self.auth_host.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl Segment for Openstack {
+    fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()> {
+        if let (Some(cloud_name), Some(Err(_))) = (&self.cloud_name, &self.cloud) {
+            w.set_bg(Color::from_rgb(255, 0, 0))?;
+            w.set_fg(Color::from_rgb(255, 255, 255))?;
+            return write!(w, " ⏹  {} ☠ ", cloud_name);
+        }
+        if self.cloud_name.is_none() && self.project_name.is_none() {
+            return Ok(());
+        }
+        let text = template::render(&self.format, |name| self.lookup(name));
+        let remaining = self
+            .token_expires
+            .map(|expires| (expires - Utc::now()).num_seconds());
+        let bg = match remaining {
+            Some(remaining) if remaining <= 0 => Color::from_rgb(200, 30, 30),
+            _ => Color::from_rgb(80, 80, 255),
+        };
+        w.set_bg(bg)?;
+        w.set_fg(Color::from_rgb(255, 255, 255))?;
+        write!(w, " ⏹  {} ", text)?;
+        match remaining {
+            Some(remaining) if remaining <= 0 => write!(w, "⌛ expired ")?,
+            Some(remaining) => write!(w, "⏳ {} ", format_remaining(remaining))?,
+            None => {}
+        }
+        Ok(())
+    }
+}
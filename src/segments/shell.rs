@@ -0,0 +1,47 @@
+use crate::{colored_stream::sanitize, Color, ColoredStream, Segment};
+use std::{env, fs, io::Write};
+
+/// The shell we're running in, shown when it's nested deeper than `POWERLINE_SHELL_THRESHOLD`
+pub struct Shell {
+    level: u32,
+    threshold: u32,
+    /// `comm` of our parent, which is the shell drawing the prompt
+    name: Option<String>,
+}
+
+impl Shell {
+    pub fn new() -> Self {
+        let level = env::var("SHLVL")
+            .ok()
+            .and_then(|level| level.trim().parse().ok())
+            .unwrap_or(0);
+        let threshold = env::var("POWERLINE_SHELL_THRESHOLD")
+            .ok()
+            .and_then(|threshold| threshold.parse().ok())
+            .unwrap_or(1);
+        let ppid = unsafe { libc::getppid() };
+        let name = fs::read(format!("/proc/{}/comm", ppid))
+            .ok()
+            .map(|comm| sanitize(comm.trim_ascii_end()))
+            .filter(|name| !name.is_empty());
+        Self {
+            level,
+            threshold,
+            name,
+        }
+    }
+}
+
+impl Segment for Shell {
+    fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()> {
+        if self.level <= self.threshold {
+            return Ok(());
+        }
+        w.set_bg(Color::from_rgb(90, 70, 40))?;
+        w.set_fg(Color::from_rgb(230, 230, 230))?;
+        match self.name {
+            Some(ref name) => write!(w, " {} ↳{} ", name, self.level),
+            None => write!(w, " ↳{} ", self.level),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+/// Expand `{name}` placeholders in `template` with the values returned by `lookup`
+///
+/// Text enclosed in `[...]` is only kept if all placeholders inside it have a value,
+/// so `{project}[@{region}]` renders as `demo` when there is no region.
+pub fn render<'a>(template: &str, lookup: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut out = String::new();
+    // text of the current `[...]` group and whether all its placeholders were found
+    let mut group: Option<(String, bool)> = None;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        let text = match c {
+            '[' if group.is_none() => {
+                group = Some((String::new(), true));
+                continue;
+            }
+            ']' if group.is_some() => {
+                if let Some((text, true)) = group.take() {
+                    out.push_str(&text);
+                }
+                continue;
+            }
+            '{' => {
+                let name = chars.by_ref().take_while(|&c| c != '}').collect::<String>();
+                match (lookup(&name), &mut group) {
+                    (Some(value), _) => value.to_string(),
+                    (None, Some((_, complete))) => {
+                        *complete = false;
+                        continue;
+                    }
+                    (None, None) => continue,
+                }
+            }
+            c => c.to_string(),
+        };
+        match group {
+            Some((ref mut group, _)) => group.push_str(&text),
+            None => out.push_str(&text),
+        }
+    }
+    if let Some((text, true)) = group {
+        out.push_str(&text);
+    }
+    out
+}
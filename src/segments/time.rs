@@ -1,34 +1,134 @@
 use crate::{Color, ColoredStream, Segment};
-use chrono::Timelike;
-use std::io::Write as _;
+use chrono::{
+    format::{Item, StrftimeItems},
+    NaiveDate, NaiveDateTime, Timelike,
+};
+use std::{env, ffi::OsString, io::Write as _, mem, path::Path};
 
-pub struct Time;
+pub struct Time {
+    format: String,
+    clock: bool,
+    twelve_hour: bool,
+    /// `POWERLINE_TIME_ZONES`, comma separated `label=zone` pairs, e.g. `UTC=UTC,NYC=America/New_York`
+    zones: Vec<(String, String)>,
+}
+
+impl Time {
+    pub fn new() -> Self {
+        let twelve_hour = super::env_flag("POWERLINE_TIME_12H");
+        let default_format = if twelve_hour {
+            "%I:%M:%S.%3f %p"
+        } else {
+            "%T.%3f"
+        };
+        let zones = env::var("POWERLINE_TIME_ZONES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|zone| zone.split_once('='))
+            .map(|(label, zone)| (label.trim().to_string(), zone.trim().to_string()))
+            .collect();
+        Self {
+            format: env::var("POWERLINE_TIME_FORMAT")
+                .ok()
+                .filter(|format| is_valid_format(format))
+                .unwrap_or_else(|| default_format.into()),
+            clock: env::var_os("POWERLINE_TIME_CLOCK").is_none_or(|clock| clock != "0"),
+            twelve_hour,
+            zones,
+        }
+    }
+}
+
+/// chrono only reports unknown specifiers when formatting, which makes `write!` panic
+fn is_valid_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
+
+/// Clock face closest to `hour:minute`
+///
+/// source: https://www.alt-codes.net/clock-symbols
+fn clock_face(hour: u32, minute: u32) -> char {
+    // number of half hours, rounded to the nearest one
+    let halves = (hour * 60 + minute + 15) / 30;
+    let hour = halves / 2 % 12;
+    let half = halves % 2 == 1;
+    // faces start at one o'clock, the half-hour ones follow the full hours
+    let code = (hour + 11) % 12 + 0x1F550 + if half { 12 } else { 0 };
+    char::from_u32(code).unwrap_or('?')
+}
+
+extern "C" {
+    fn tzset();
+}
+
+/// Current time in `zone` using the system's timezone database
+///
+/// There is no way to ask libc about another zone than `TZ`, so it is swapped for a moment.
+fn time_in_zone(zone: &str) -> Option<NaiveDateTime> {
+    // libc silently falls back to UTC for unknown names, POSIX rules like `EST5EDT` have an offset
+    let tzdir = env::var_os("TZDIR").unwrap_or_else(|| "/usr/share/zoneinfo".into());
+    if !zone.contains(|c: char| c.is_ascii_digit()) && !Path::new(&tzdir).join(zone).is_file() {
+        return None;
+    }
+    let original: Option<OsString> = env::var_os("TZ");
+    env::set_var("TZ", zone);
+    let tm = unsafe {
+        tzset();
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = mem::zeroed();
+        let tm = if libc::localtime_r(&now, &mut tm).is_null() {
+            None
+        } else {
+            Some(tm)
+        };
+        match original {
+            Some(ref original) => env::set_var("TZ", original),
+            None => env::remove_var("TZ"),
+        }
+        tzset();
+        tm
+    }?;
+    NaiveDate::from_ymd_opt(tm.tm_year + 1900, tm.tm_mon as u32 + 1, tm.tm_mday as u32)?
+        .and_hms_opt(tm.tm_hour as u32, tm.tm_min as u32, tm.tm_sec as u32)
+}
 
 impl Segment for Time {
     fn write(&mut self, w: &mut ColoredStream) -> std::io::Result<()> {
         w.set_bg(Color::from_rgb(80, 80, 80))?;
         w.set_fg(Color::from_rgb(200, 200, 200))?;
-        let time = chrono::Local::now().time();
-
-        let minute = time.minute();
-        let (hour, half) = {
-            let (_, hour) = time.hour12();
-            if minute >= 45 {
-                (hour + 1, false)
-            } else if minute > 15 {
-                (hour, true)
-            } else {
-                (hour, false)
+        let now = chrono::Local::now();
+
+        write!(w, " ")?;
+        if self.clock {
+            write!(w, "{} ", clock_face(now.hour(), now.minute()))?;
+        }
+        write!(w, "{} ", now.format(&self.format))?;
+
+        let zone_format = if self.twelve_hour { "%I:%M%p" } else { "%H:%M" };
+        for (label, zone) in &self.zones {
+            w.start_segment(Color::from_rgb(65, 65, 65))?;
+            w.set_fg(Color::from_rgb(200, 200, 200))?;
+            match time_in_zone(zone) {
+                Some(time) => write!(w, " {} {} ", label, time.format(zone_format))?,
+                None => write!(w, " {} ? ", label)?,
             }
-        };
-        // source: https://www.alt-codes.net/clock-symbols
-        let code = (hour - 1) % 12 + 128336 + if half { 12 } else { 0 };
-        write!(
-            w,
-            " {} {} ",
-            unsafe { char::from_u32_unchecked(code) },
-            chrono::Local::now().format("%T.%3f")
-        )?;
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::clock_face;
+
+    #[test]
+    fn clock_face_rounds_to_nearest_half_hour() {
+        assert_eq!(clock_face(0, 0), '🕛');
+        assert_eq!(clock_face(11, 45), '🕛');
+        assert_eq!(clock_face(12, 50), '🕐');
+        assert_eq!(clock_face(23, 50), '🕛');
+        assert_eq!(clock_face(12, 30), '🕧');
+        assert_eq!(clock_face(16, 31), '🕟');
+        assert_eq!(clock_face(9, 14), '🕘');
+    }
+}
@@ -1,14 +1,256 @@
 use crate::{Color, ColoredStream, Segment};
-use std::io::Write as _;
+use std::{
+    env,
+    ffi::{CStr, CString, OsStr, OsString},
+    fs,
+    io::{IsTerminal, Write as _},
+    mem,
+    os::unix::{ffi::OsStrExt, fs::MetadataExt},
+    path::PathBuf,
+    ptr,
+};
+
+const SUDO_TS_DIR: &str = "/run/sudo/ts";
 
 pub struct User {
     is_root: bool,
+    name: OsString,
+    /// Who we really are, if it differs from the effective user (setuid, sudo)
+    real_name: Option<OsString>,
+    sudo_cached: bool,
+    /// `POWERLINE_USER_DEFAULT` matches, only `default_icon` is shown
+    is_default: bool,
+    default_icon: Option<String>,
+    /// Sensitive groups we are a member of
+    groups: Vec<String>,
+}
+
+/// Name of `uid` in the user database, `None` for users that aren't there,
+/// which is common for arbitrary UIDs in containers
+fn lookup_name(uid: libc::uid_t) -> Option<OsString> {
+    let mut buf = vec![0 as libc::c_char; 1024];
+    let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+    let mut result = ptr::null_mut();
+    loop {
+        let ret =
+            unsafe { libc::getpwuid_r(uid, &mut passwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if ret == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if ret != 0 || result.is_null() || passwd.pw_name.is_null() {
+            return None;
+        }
+        break;
+    }
+    let name = unsafe { CStr::from_ptr(passwd.pw_name) };
+    Some(OsStr::from_bytes(name.to_bytes()).to_owned())
+}
+
+/// Falls back to `$USER` for our own UID and to the number itself
+fn user_name(uid: libc::uid_t) -> OsString {
+    lookup_name(uid)
+        .or_else(|| {
+            if uid == unsafe { libc::getuid() } {
+                env::var_os("USER").filter(|user| !user.is_empty())
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| uid.to_string().into())
+}
+
+/// Whether `sudo` would run without asking for a password in this terminal
+///
+/// Reads the records sudo keeps in `<dir>/<user>`, the directory defaults to `/run/sudo/ts`
+/// and can be set with `POWERLINE_SUDO_TS_DIR`. sudo creates both the directory and the
+/// records for root only, so for the indicator to ever show up the user needs read access,
+/// e.g. through a default ACL: `setfacl -m u:<user>:x -d -m u:<user>:r /run/sudo/ts`.
+/// Without it the records can't be read and no indicator is shown.
+fn sudo_cached(user: &OsStr) -> bool {
+    let dir = env::var_os("POWERLINE_SUDO_TS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(SUDO_TS_DIR));
+    let data = match fs::read(dir.join(user)) {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+    // `timestamp_timeout` from sudoers, in minutes
+    let timeout = env::var("POWERLINE_SUDO_TIMEOUT")
+        .ok()
+        .and_then(|timeout| timeout.parse::<f64>().ok())
+        .unwrap_or(15.0);
+    // sudo stamps records with its "awake" clock, which doesn't advance during suspend
+    let now = unsafe {
+        let mut now: libc::timespec = mem::zeroed();
+        if libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) != 0 {
+            return false;
+        }
+        now
+    };
+    let tty = if std::io::stdin().is_terminal() {
+        fs::metadata("/dev/stdin").ok().map(|meta| meta.rdev())
+    } else {
+        None
+    };
+    let (sid, ppid) = unsafe { (libc::getsid(0), libc::getppid()) };
+
+    let mut rest = &data[..];
+    while let Some(entry) = TimestampEntry::parse(rest) {
+        rest = &rest[entry.size..];
+        let matches = match entry.kind {
+            TS_GLOBAL => true,
+            // the start time tells apart a session or parent that reuses an old pid
+            TS_TTY => {
+                entry.sid == sid
+                    && Some(entry.tty_or_ppid) == tty
+                    && Some(entry.start_time) == process_start_time(sid)
+            }
+            TS_PPID => {
+                entry.sid == sid
+                    && entry.tty_or_ppid == ppid as u64
+                    && Some(entry.start_time) == process_start_time(ppid)
+            }
+            _ => false,
+        };
+        let age = (now.tv_sec - entry.ts_sec) as f64;
+        if matches && entry.flags & TS_DISABLED == 0 && age >= 0.0 && age < timeout * 60.0 {
+            return true;
+        }
+    }
+    false
+}
+
+/// When `pid` started, converted from clock ticks the same way sudo does
+fn process_start_time(pid: libc::pid_t) -> Option<(i64, i64)> {
+    let stat = fs::read(format!("/proc/{}/stat", pid)).ok()?;
+    // `<pid> (<comm>) <state> ...`, comm can contain anything, `starttime` is field 22
+    let close = stat.iter().rposition(|&b| b == b')')?;
+    let rest = std::str::from_utf8(stat.get(close + 1..)?).ok()?;
+    let ticks: i64 = rest.split_whitespace().nth(19)?.parse().ok()?;
+    let hz = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if hz <= 0 {
+        return None;
+    }
+    Some((ticks / hz, ticks % hz * (1_000_000_000 / hz)))
+}
+
+const TS_GLOBAL: u16 = 1;
+const TS_TTY: u16 = 2;
+const TS_PPID: u16 = 3;
+const TS_DISABLED: u16 = 0x01;
+
+/// The fields we need of sudo's `struct timestamp_entry`, as laid out on 64-bit Linux
+struct TimestampEntry {
+    size: usize,
+    kind: u16,
+    flags: u16,
+    sid: libc::pid_t,
+    /// Start of the session leader or parent, as `(seconds, nanoseconds)` since boot
+    start_time: (i64, i64),
+    ts_sec: libc::time_t,
+    tty_or_ppid: u64,
+}
+
+impl TimestampEntry {
+    const LEN: usize = 56;
+
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::LEN {
+            return None;
+        }
+        let u16_at = |at: usize| u16::from_ne_bytes([data[at], data[at + 1]]);
+        let bytes = |at: usize, len: usize| &data[at..at + len];
+        let size = u16_at(2) as usize;
+        if u16_at(0) != 2 || size < Self::LEN || size > data.len() {
+            return None;
+        }
+        Some(Self {
+            size,
+            kind: u16_at(4),
+            flags: u16_at(6),
+            sid: i32::from_ne_bytes(bytes(12, 4).try_into().ok()?),
+            start_time: (
+                i64::from_ne_bytes(bytes(16, 8).try_into().ok()?),
+                i64::from_ne_bytes(bytes(24, 8).try_into().ok()?),
+            ),
+            ts_sec: i64::from_ne_bytes(bytes(32, 8).try_into().ok()?),
+            tty_or_ppid: match u16_at(4) {
+                TS_PPID => i32::from_ne_bytes(bytes(48, 4).try_into().ok()?) as u64,
+                _ => u64::from_ne_bytes(bytes(48, 8).try_into().ok()?),
+            },
+        })
+    }
+}
+
+/// ID of the group called `name`
+fn group_id(name: &str) -> Option<libc::gid_t> {
+    let name = CString::new(name).ok()?;
+    let mut buf = vec![0 as libc::c_char; 1024];
+    let mut group: libc::group = unsafe { mem::zeroed() };
+    let mut result = ptr::null_mut();
+    loop {
+        let ret = unsafe {
+            libc::getgrnam_r(
+                name.as_ptr(),
+                &mut group,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        if ret == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if ret != 0 || result.is_null() {
+            return None;
+        }
+        return Some(group.gr_gid);
+    }
+}
+
+/// Groups that are effectively root, `POWERLINE_USER_GROUPS` overrides the default list
+fn sensitive_groups() -> Vec<String> {
+    let names = env::var("POWERLINE_USER_GROUPS").unwrap_or_else(|_| "docker,wheel,kvm".into());
+    let gids = unsafe {
+        let count = libc::getgroups(0, ptr::null_mut());
+        let mut gids = vec![0; count.max(0) as usize];
+        let count = libc::getgroups(gids.len() as libc::c_int, gids.as_mut_ptr());
+        gids.truncate(count.max(0) as usize);
+        gids.push(libc::getegid());
+        gids
+    };
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| group_id(name).is_some_and(|gid| gids.contains(&gid)))
+        .map(str::to_string)
+        .collect()
 }
 
 impl User {
     pub fn new() -> Self {
+        let (uid, euid) = unsafe { (libc::getuid(), libc::geteuid()) };
+        let name = user_name(euid);
+        let real_name = if uid != euid {
+            Some(user_name(uid))
+        } else {
+            // `sudo` changes both, but leaves a note who invoked it
+            env::var_os("SUDO_USER").filter(|sudo_user| !sudo_user.is_empty() && *sudo_user != name)
+        };
+        let sudo_cached = super::env_flag("POWERLINE_USER_SUDO")
+            && sudo_cached(real_name.as_deref().unwrap_or(&name));
+        let is_default = real_name.is_none()
+            && env::var_os("POWERLINE_USER_DEFAULT").is_some_and(|default| default == name);
         Self {
-            is_root: unsafe { libc::getuid() == 0 },
+            is_root: euid == 0,
+            name,
+            real_name,
+            sudo_cached,
+            is_default,
+            default_icon: env::var("POWERLINE_USER_DEFAULT_ICON").ok(),
+            groups: sensitive_groups(),
         }
     }
 }
@@ -20,22 +262,32 @@ impl Segment for User {
         } else {
             Color::from_rgb(60, 100, 100)
         };
-        w.set_bg(bg)?;
-        w.set_fg(Color::from_rgb(230, 230, 230))?;
-        // write!(w, " 👤 ")?;
-        write!(w, " ")?;
-        unsafe {
-            let pw = libc::getpwuid(libc::getuid());
-            let mut name: *mut libc::c_char = (*pw).pw_name;
-            loop {
-                if *name == 0 {
-                    break;
-                }
-                write!(w, "{}", *name as u8 as char)?;
-                name = name.add(1);
+        if !self.is_default {
+            w.set_bg(bg)?;
+            w.set_fg(Color::from_rgb(230, 230, 230))?;
+            // write!(w, " 👤 ")?;
+            write!(w, " ")?;
+            if let Some(ref real_name) = self.real_name {
+                w.write_all(real_name.as_bytes())?;
+                write!(w, " ➜ ")?;
             }
-        };
-        write!(w, " ")?;
+            w.write_all(self.name.as_bytes())?;
+            write!(w, " ")?;
+        } else if let Some(ref icon) = self.default_icon {
+            w.set_bg(bg)?;
+            w.set_fg(Color::from_rgb(230, 230, 230))?;
+            write!(w, " {} ", icon)?;
+        }
+        if !self.groups.is_empty() {
+            w.start_segment(Color::from_rgb(150, 60, 150))?;
+            w.set_fg(Color::from_rgb(255, 255, 255))?;
+            write!(w, " ⚠ {} ", self.groups.join(","))?;
+        }
+        if self.sudo_cached {
+            w.start_segment(Color::from_rgb(255, 140, 0))?;
+            w.set_fg(Color::from_rgb(0, 0, 0))?;
+            write!(w, " ⚡sudo ")?;
+        }
         Ok(())
     }
 }